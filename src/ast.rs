@@ -1,15 +1,20 @@
 #![allow(dead_code)]
 #![allow(unused_imports)]
 
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
 use crate::lexer::Lexer;
-use crate::token::Token;
+use crate::parser::{Parser, Precedence};
+use crate::token::{Span, Token};
 
+//the ast owns its names rather than borrowing them from the source, so a program can be kept after the source is dropped (e.g. cached), or sent to another thread
 #[derive(Debug, PartialEq)]
-pub struct Program<'source> {
-    pub statements: Vec<Statement<'source>>,
+pub struct Program {
+    pub statements: Vec<Spanned<Statement>>,
 }
 
-impl<'source> Program<'source> {
+impl Program {
     pub fn new() -> Self {
         Self {
             //unknown number of statments so should just be a vector
@@ -18,22 +23,812 @@ impl<'source> Program<'source> {
     }
 }
 
-impl<'source> Default for Program<'source> {
+impl Default for Program {
     fn default() -> Self {
         Self::new()
     }
 }
 
+//a node of the ast, and the span of the source it was parsed from - e.g. so errors and warnings can say where they are.
+//spans are ignored when nodes are compared, so an ast can still be checked against one built by hand or one parsed from different source (e.g. after being printed) - check the span field itself to test where a node is
+#[derive(Clone, Copy, Default)]
+pub struct Spanned<T> {
+    pub node: T,
+    pub span: Span,
+}
+
+impl<T> Spanned<T> {
+    pub fn new(node: T, span: Span) -> Self {
+        Self { node, span }
+    }
+
+    //changes the node, keeping its span
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Spanned<U> {
+        Spanned::new(f(self.node), self.span)
+    }
+}
+
+impl<T> Deref for Spanned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.node
+    }
+}
+
+impl<T> DerefMut for Spanned<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.node
+    }
+}
+
+//a node with an empty span at the start of the source - for building an ast by hand, e.g. in tests
+impl<T> From<T> for Spanned<T> {
+    fn from(node: T) -> Self {
+        Self::new(node, Span::default())
+    }
+}
+
+impl<T: PartialEq> PartialEq for Spanned<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.node == other.node
+    }
+}
+
+impl<T: Eq> Eq for Spanned<T> {}
+
+//the node followed by its span, e.g. "Identifier(\"x\") @ 4..5", which keeps printed asts readable
+impl<T: fmt::Debug> fmt::Debug for Spanned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.node.fmt(f)?;
+        write!(f, " @ {}..{}", self.span.start, self.span.end)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Spanned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_node(f, &self.node)
+    }
+}
+
 //[TODO] - add more types in future
-#[derive(Debug, PartialEq, Clone, Copy)]
-pub enum Statement<'source> {
-    Let(&'source str, Expression<'source>),
-    Return(Expression<'source>),
+#[derive(Debug, PartialEq, Clone)]
+pub enum Statement {
+    Let(Spanned<String>, Spanned<Expression>),
+    Return(Spanned<Expression>),
+    //an expression on its own as a statement - e.g. "x + 10;"
+    Expression(Spanned<Expression>),
+}
+
+//a list of statements between braces - e.g. the body of a function or an if expression
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct BlockStatement {
+    pub statements: Vec<Spanned<Statement>>,
 }
 
 //[TODO] - add more types in future
-#[derive(Debug, PartialEq, Clone, Copy)]
-pub enum Expression<'source> {
-    Identifier(&'source str),
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expression {
+    Identifier(String),
     Integer(i32),
+    Boolean(bool),
+    //e.g. "-5", "!true"
+    Prefix(Prefix, Box<Spanned<Expression>>),
+    //e.g. "5 + 5", "a && b" - the left expression is first
+    Infix(Infix, Box<Spanned<Expression>>, Box<Spanned<Expression>>),
+    //e.g. "if (x < y) { x } else { y }"
+    If {
+        condition: Box<Spanned<Expression>>,
+        consequence: Spanned<BlockStatement>,
+        alternative: Option<Spanned<BlockStatement>>,
+    },
+    //e.g. "fn(x, y) { x + y }"
+    Function {
+        parameters: Vec<Spanned<String>>,
+        body: Spanned<BlockStatement>,
+    },
+    //e.g. "add(1, 2)", "fn(x) { x }(5)"
+    Call {
+        function: Box<Spanned<Expression>>,
+        arguments: Vec<Spanned<Expression>>,
+    },
+    //e.g. "x = x + 1" - changes the value of the nearest binding of the name, and gives the new value.
+    //only the tree-walking evaluator runs it, as there is no compiler or vm yet
+    Assign(Spanned<String>, Box<Spanned<Expression>>),
+}
+
+//every operator that can go before an expression
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Prefix {
+    Bang,  //"!"
+    Minus, //"-"
+}
+
+//every operator that can go between two expressions
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Infix {
+    Plus,             //"+"
+    Minus,            //"-"
+    Asterisk,         //"*"
+    Slash,            //"/"
+    Percent,          //"%"
+    LessThan,         //"<"
+    GreaterThan,      //">"
+    LessThanEqual,    //"<="
+    GreaterThanEqual, //">="
+    Equal,            //"=="
+    NotEqual,         //"!="
+    And,              //"&&"
+    Or,               //"||"
+}
+
+impl Infix {
+    //how tightly the operator binds - the same as the parser uses for the token it comes from
+    pub fn precedence(self) -> Precedence {
+        match self {
+            Infix::Or => Precedence::Or,
+            Infix::And => Precedence::And,
+            Infix::Equal | Infix::NotEqual => Precedence::Equals,
+            Infix::LessThan
+            | Infix::GreaterThan
+            | Infix::LessThanEqual
+            | Infix::GreaterThanEqual => Precedence::LessGreater,
+            Infix::Plus | Infix::Minus => Precedence::Sum,
+            Infix::Asterisk | Infix::Slash | Infix::Percent => Precedence::Product,
+        }
+    }
+}
+
+impl Expression {
+    //how tightly the expression holds together next to an operator when printed - anything not built from an operator can go anywhere without brackets
+    pub fn precedence(&self) -> Precedence {
+        match self {
+            Expression::Prefix(..) => Precedence::Prefix,
+            Expression::Infix(operator, ..) => operator.precedence(),
+            Expression::Assign(..) => Precedence::Assign,
+            _ => Precedence::Call,
+        }
+    }
+}
+
+//writes a node inside another, keeping the alternate flag so the whole tree is printed the same way
+fn write_node(f: &mut fmt::Formatter<'_>, node: &impl fmt::Display) -> fmt::Result {
+    if f.alternate() {
+        write!(f, "{node:#}")
+    } else {
+        write!(f, "{node}")
+    }
+}
+
+//writes an operand of an operator or call - in brackets if it would otherwise be parsed as part of something else.
+//fully bracketed output has no need, as every operator brings its own brackets
+fn write_operand(
+    f: &mut fmt::Formatter<'_>,
+    operand: &Expression,
+    needs_brackets: bool,
+) -> fmt::Result {
+    if needs_brackets && !f.alternate() {
+        write!(f, "({operand})")
+    } else {
+        write_node(f, operand)
+    }
+}
+
+//writes each item with ", " between them - e.g. the arguments of a call
+fn write_list(f: &mut fmt::Formatter<'_>, items: &[impl fmt::Display]) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write_node(f, item)?;
+    }
+    Ok(())
+}
+
+//the ast is printed back out as monkey source, which parses back into the same ast.
+//only the brackets needed to keep the order things are evaluated in are printed, e.g. "-a * (b + c)" - the alternate flag ("{:#}") brackets every operator instead, e.g. "((-a) * (b + c))"
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, statement) in self.statements.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write_node(f, statement)?;
+        }
+        Ok(())
+    }
+}
+
+//every statement ends with a ";", so an expression statement can't carry on into the one after it (e.g. "a" then "-b")
+impl fmt::Display for Statement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Statement::Let(name, value) => {
+                write!(f, "let {name} = ")?;
+                write_node(f, value)?;
+            }
+            Statement::Return(value) => {
+                write!(f, "return ")?;
+                write_node(f, value)?;
+            }
+            Statement::Expression(expression) => write_node(f, expression)?,
+        }
+        write!(f, ";")
+    }
+}
+
+//blocks are printed on one line, e.g. "{ let y = x * 2; y; }"
+impl fmt::Display for BlockStatement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.statements.is_empty() {
+            return write!(f, "{{}}");
+        }
+
+        write!(f, "{{")?;
+        for statement in &self.statements {
+            write!(f, " ")?;
+            write_node(f, statement)?;
+        }
+        write!(f, " }}")
+    }
+}
+
+impl fmt::Display for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expression::Identifier(name) => write!(f, "{name}"),
+            Expression::Integer(value) => write!(f, "{value}"),
+            Expression::Boolean(value) => write!(f, "{value}"),
+            Expression::Prefix(operator, right) if f.alternate() => {
+                write!(f, "({operator}{right:#})")
+            }
+            Expression::Prefix(operator, right) => {
+                write!(f, "{operator}")?;
+                write_operand(f, right, right.precedence() < Precedence::Prefix)
+            }
+            Expression::Infix(operator, left, right) if f.alternate() => {
+                write!(f, "({left:#} {operator} {right:#})")
+            }
+            //operators group to the left, so an operand on the right with the same precedence needs brackets - e.g. "a - (b - c)"
+            Expression::Infix(operator, left, right) => {
+                let precedence: Precedence = operator.precedence();
+                write_operand(f, left, left.precedence() < precedence)?;
+                write!(f, " {operator} ")?;
+                write_operand(f, right, right.precedence() <= precedence)
+            }
+            Expression::If {
+                condition,
+                consequence,
+                alternative,
+            } => {
+                write!(f, "if (")?;
+                write_node(f, condition.as_ref())?;
+                write!(f, ") ")?;
+                write_node(f, consequence)?;
+                if let Some(alternative) = alternative {
+                    write!(f, " else ")?;
+                    write_node(f, alternative)?;
+                }
+                Ok(())
+            }
+            Expression::Function { parameters, body } => {
+                write!(f, "fn(")?;
+                write_list(f, parameters)?;
+                write!(f, ") ")?;
+                write_node(f, body)
+            }
+            Expression::Call {
+                function,
+                arguments,
+            } => {
+                write_operand(f, function, function.precedence() < Precedence::Call)?;
+                write!(f, "(")?;
+                write_list(f, arguments)?;
+                write!(f, ")")
+            }
+            Expression::Assign(name, value) if f.alternate() => write!(f, "({name} = {value:#})"),
+            //assignments group to the right, so "a = b = c" needs no brackets
+            Expression::Assign(name, value) => {
+                write!(f, "{name} = ")?;
+                write_node(f, value.as_ref())
+            }
+        }
+    }
+}
+
+impl fmt::Display for Prefix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol: &str = match self {
+            Prefix::Bang => "!",
+            Prefix::Minus => "-",
+        };
+        write!(f, "{symbol}")
+    }
+}
+
+impl fmt::Display for Infix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol: &str = match self {
+            Infix::Plus => "+",
+            Infix::Minus => "-",
+            Infix::Asterisk => "*",
+            Infix::Slash => "/",
+            Infix::Percent => "%",
+            Infix::LessThan => "<",
+            Infix::GreaterThan => ">",
+            Infix::LessThanEqual => "<=",
+            Infix::GreaterThanEqual => ">=",
+            Infix::Equal => "==",
+            Infix::NotEqual => "!=",
+            Infix::And => "&&",
+            Infix::Or => "||",
+        };
+        write!(f, "{symbol}")
+    }
+}
+
+//walks the ast without changing it - each visit_* method is called on a node of its type (along with its span), and by default walks into the nodes inside it with the matching walk_* method.
+//an implementation only overrides the nodes it cares about, and calls walk_* itself if it still wants to see the nodes inside them.
+//names aren't expressions, so have hooks of their own - visit_name for the name a let binds or an assignment updates, and visit_parameter for each parameter of a function
+pub trait Visitor<'ast> {
+    fn visit_program(&mut self, program: &'ast Program) {
+        self.walk_program(program);
+    }
+
+    fn visit_statement(&mut self, statement: &'ast Spanned<Statement>) {
+        self.walk_statement(statement);
+    }
+
+    fn visit_block(&mut self, block: &'ast Spanned<BlockStatement>) {
+        self.walk_block(block);
+    }
+
+    fn visit_expression(&mut self, expression: &'ast Spanned<Expression>) {
+        self.walk_expression(expression);
+    }
+
+    fn visit_name(&mut self, _name: &'ast Spanned<String>) {}
+
+    fn visit_parameter(&mut self, _parameter: &'ast Spanned<String>) {}
+
+    fn walk_program(&mut self, program: &'ast Program) {
+        for statement in &program.statements {
+            self.visit_statement(statement);
+        }
+    }
+
+    fn walk_statement(&mut self, statement: &'ast Spanned<Statement>) {
+        match &statement.node {
+            Statement::Let(name, value) => {
+                self.visit_name(name);
+                self.visit_expression(value);
+            }
+            Statement::Return(value) => self.visit_expression(value),
+            Statement::Expression(expression) => self.visit_expression(expression),
+        }
+    }
+
+    fn walk_block(&mut self, block: &'ast Spanned<BlockStatement>) {
+        for statement in &block.statements {
+            self.visit_statement(statement);
+        }
+    }
+
+    //visits the nodes inside the expression in the order they are written
+    fn walk_expression(&mut self, expression: &'ast Spanned<Expression>) {
+        match &expression.node {
+            Expression::Identifier(_) | Expression::Integer(_) | Expression::Boolean(_) => {}
+            Expression::Prefix(_, right) => self.visit_expression(right),
+            Expression::Infix(_, left, right) => {
+                self.visit_expression(left);
+                self.visit_expression(right);
+            }
+            Expression::If {
+                condition,
+                consequence,
+                alternative,
+            } => {
+                self.visit_expression(condition);
+                self.visit_block(consequence);
+                if let Some(alternative) = alternative {
+                    self.visit_block(alternative);
+                }
+            }
+            Expression::Function { parameters, body } => {
+                for parameter in parameters {
+                    self.visit_parameter(parameter);
+                }
+                self.visit_block(body);
+            }
+            Expression::Call {
+                function,
+                arguments,
+            } => {
+                self.visit_expression(function);
+                for argument in arguments {
+                    self.visit_expression(argument);
+                }
+            }
+            Expression::Assign(name, value) => {
+                self.visit_name(name);
+                self.visit_expression(value);
+            }
+        }
+    }
+}
+
+//the same as Visitor, but can change the nodes as it goes - e.g. to replace an expression with a simpler one
+pub trait VisitorMut {
+    fn visit_program_mut(&mut self, program: &mut Program) {
+        self.walk_program_mut(program);
+    }
+
+    fn visit_statement_mut(&mut self, statement: &mut Spanned<Statement>) {
+        self.walk_statement_mut(statement);
+    }
+
+    fn visit_block_mut(&mut self, block: &mut Spanned<BlockStatement>) {
+        self.walk_block_mut(block);
+    }
+
+    fn visit_expression_mut(&mut self, expression: &mut Spanned<Expression>) {
+        self.walk_expression_mut(expression);
+    }
+
+    fn visit_name_mut(&mut self, _name: &mut Spanned<String>) {}
+
+    fn visit_parameter_mut(&mut self, _parameter: &mut Spanned<String>) {}
+
+    fn walk_program_mut(&mut self, program: &mut Program) {
+        for statement in &mut program.statements {
+            self.visit_statement_mut(statement);
+        }
+    }
+
+    fn walk_statement_mut(&mut self, statement: &mut Spanned<Statement>) {
+        match &mut statement.node {
+            Statement::Let(name, value) => {
+                self.visit_name_mut(name);
+                self.visit_expression_mut(value);
+            }
+            Statement::Return(value) => self.visit_expression_mut(value),
+            Statement::Expression(expression) => self.visit_expression_mut(expression),
+        }
+    }
+
+    fn walk_block_mut(&mut self, block: &mut Spanned<BlockStatement>) {
+        for statement in &mut block.statements {
+            self.visit_statement_mut(statement);
+        }
+    }
+
+    fn walk_expression_mut(&mut self, expression: &mut Spanned<Expression>) {
+        match &mut expression.node {
+            Expression::Identifier(_) | Expression::Integer(_) | Expression::Boolean(_) => {}
+            Expression::Prefix(_, right) => self.visit_expression_mut(right),
+            Expression::Infix(_, left, right) => {
+                self.visit_expression_mut(left);
+                self.visit_expression_mut(right);
+            }
+            Expression::If {
+                condition,
+                consequence,
+                alternative,
+            } => {
+                self.visit_expression_mut(condition);
+                self.visit_block_mut(consequence);
+                if let Some(alternative) = alternative {
+                    self.visit_block_mut(alternative);
+                }
+            }
+            Expression::Function { parameters, body } => {
+                for parameter in parameters {
+                    self.visit_parameter_mut(parameter);
+                }
+                self.visit_block_mut(body);
+            }
+            Expression::Call {
+                function,
+                arguments,
+            } => {
+                self.visit_expression_mut(function);
+                for argument in arguments {
+                    self.visit_expression_mut(argument);
+                }
+            }
+            Expression::Assign(name, value) => {
+                self.visit_name_mut(name);
+                self.visit_expression_mut(value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+fn parse(input: &str) -> Program {
+    let mut parser: Parser = Parser::new(Lexer::new(input));
+    let program: Program = parser.parse_program();
+    assert!(parser.errors().is_empty(), "{input}: {:?}", parser.errors());
+    program
+}
+
+#[test]
+fn test_display() {
+    //(input, minimal brackets, every operator bracketed)
+    let tests: Vec<(&str, &str, &str)> = vec![
+        ("-a * b", "-a * b;", "((-a) * b);"),
+        ("-(a * b)", "-(a * b);", "(-(a * b));"),
+        ("a + b * c", "a + b * c;", "(a + (b * c));"),
+        ("(a + b) * c", "(a + b) * c;", "((a + b) * c);"),
+        ("a - b - c", "a - b - c;", "((a - b) - c);"),
+        ("a - (b - c)", "a - (b - c);", "(a - (b - c));"),
+        (
+            "!!true == false",
+            "!!true == false;",
+            "((!(!true)) == false);",
+        ),
+        ("a || b && c", "a || b && c;", "(a || (b && c));"),
+        ("-add(1, 2 * 3)", "-add(1, 2 * 3);", "(-add(1, (2 * 3)));"),
+        ("(-f)(x)", "(-f)(x);", "(-f)(x);"),
+        (
+            "fn(x, y) { x + y; }(1, 2)",
+            "fn(x, y) { x + y; }(1, 2);",
+            "fn(x, y) { (x + y); }(1, 2);",
+        ),
+        (
+            "let max = fn(a, b) { if (a > b) { return a; } else { b } };",
+            "let max = fn(a, b) { if (a > b) { return a; } else { b; }; };",
+            "let max = fn(a, b) { if ((a > b)) { return a; } else { b; }; };",
+        ),
+        ("x = y = 1 + 2", "x = y = 1 + 2;", "(x = (y = (1 + 2)));"),
+        (
+            "-(x = 1) * (y = 2)",
+            "-(x = 1) * (y = 2);",
+            "((-(x = 1)) * (y = 2));",
+        ),
+        (
+            "if (x) {}\nlet y = 5 % 2",
+            "if (x) {};\nlet y = 5 % 2;",
+            "if (x) {};\nlet y = (5 % 2);",
+        ),
+    ];
+
+    tests.into_iter().for_each(|(input, minimal, bracketed)| {
+        let program: Program = parse(input);
+        assert_eq!(program.to_string(), minimal, "{input}");
+        assert_eq!(format!("{program:#}"), bracketed, "{input}");
+    });
+}
+
+#[test]
+fn test_visitor() {
+    //collects every identifier used, but doesn't look inside functions
+    struct Identifiers<'ast>(Vec<&'ast str>);
+
+    impl<'ast> Visitor<'ast> for Identifiers<'ast> {
+        fn visit_expression(&mut self, expression: &'ast Spanned<Expression>) {
+            match &expression.node {
+                Expression::Identifier(name) => self.0.push(name),
+                Expression::Function { .. } => {}
+                _ => self.walk_expression(expression),
+            }
+        }
+    }
+
+    let program: Program = parse("let a = b + -c; if (d) { e(f, fn(g) { g }) } else { return h; }");
+    let mut identifiers: Identifiers = Identifiers(Vec::new());
+    identifiers.visit_program(&program);
+    assert_eq!(identifiers.0, vec!["b", "c", "d", "e", "f", "h"]);
+}
+
+#[test]
+fn test_visitor_names() {
+    //collects every name bound or assigned to, and every parameter, with where they are
+    #[derive(Default)]
+    struct Names<'ast> {
+        names: Vec<(&'ast str, usize)>,
+        parameters: Vec<(&'ast str, usize)>,
+    }
+
+    impl<'ast> Visitor<'ast> for Names<'ast> {
+        fn visit_name(&mut self, name: &'ast Spanned<String>) {
+            self.names.push((name.as_str(), name.span.start));
+        }
+
+        fn visit_parameter(&mut self, parameter: &'ast Spanned<String>) {
+            self.parameters
+                .push((parameter.as_str(), parameter.span.start));
+        }
+    }
+
+    let program: Program = parse("let f = fn(a, b) { let c = a; b = c };\nx = f(1, 2)");
+    let mut names: Names = Names::default();
+    names.visit_program(&program);
+    assert_eq!(names.names, vec![("f", 4), ("c", 23), ("b", 30), ("x", 39)]);
+    assert_eq!(names.parameters, vec![("a", 11), ("b", 14)]);
+}
+
+#[test]
+fn test_visitor_mut() {
+    //doubles every integer, replaces every "x" with 0, and renames "a" to "n" and "y" to "w"
+    struct Rewrite;
+
+    impl VisitorMut for Rewrite {
+        fn visit_expression_mut(&mut self, expression: &mut Spanned<Expression>) {
+            match &mut expression.node {
+                Expression::Integer(value) => *value *= 2,
+                Expression::Identifier(name) if name == "x" => {
+                    expression.node = Expression::Integer(0)
+                }
+                Expression::Identifier(name) if name == "a" => *name = String::from("n"),
+                _ => self.walk_expression_mut(expression),
+            }
+        }
+
+        fn visit_name_mut(&mut self, name: &mut Spanned<String>) {
+            if name.node == "y" {
+                name.node = String::from("w");
+            }
+        }
+
+        fn visit_parameter_mut(&mut self, parameter: &mut Spanned<String>) {
+            if parameter.node == "a" {
+                parameter.node = String::from("n");
+            }
+        }
+    }
+
+    let mut program: Program =
+        parse("let y = x + 1; fn(a) { if (a) { x } else { y = 2 * a } }(3);");
+    Rewrite.visit_program_mut(&mut program);
+    assert_eq!(
+        program.to_string(),
+        "let w = 0 + 2;\nfn(n) { if (n) { 0; } else { w = 4 * n; }; }(6);"
+    );
+}
+
+//the ast owns its names, so it can be kept after the source is dropped, sent to another thread, and evaluated there
+#[test]
+fn test_program_outlives_source() {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::environment::Environment;
+    use crate::evaluator::eval_program;
+    use crate::object::Object;
+
+    let source: String = String::from(
+        "let add = fn(a, b) { a + b }; let x = if (true) { add(1, 2) } else { -1 }; x * 2",
+    );
+    let program: Program = parse(&source);
+    drop(source);
+
+    let program: Program = std::thread::spawn(move || program).join().unwrap();
+    let env: Rc<RefCell<Environment>> = Rc::new(RefCell::new(Environment::new()));
+    assert_eq!(eval_program(&program, &env), Object::Integer(6));
+}
+
+#[cfg(test)]
+pub(crate) mod strategies {
+    use proptest::prelude::*;
+    use proptest::sample::select;
+
+    use super::{BlockStatement, Expression, Infix, Prefix, Program, Spanned, Statement};
+
+    //every node is given an empty span, as spans aren't compared
+    fn identifier() -> impl Strategy<Value = Spanned<String>> {
+        select(vec!["a", "b", "x", "add", "is_even2", "ünïcödé"])
+            .prop_map(|name| Spanned::from(name.to_string()))
+    }
+
+    fn block(
+        expression: impl Strategy<Value = Spanned<Expression>> + Clone,
+    ) -> impl Strategy<Value = Spanned<BlockStatement>> {
+        prop::collection::vec(statement(expression), 0..3)
+            .prop_map(|statements| BlockStatement { statements }.into())
+    }
+
+    fn statement(
+        expression: impl Strategy<Value = Spanned<Expression>> + Clone,
+    ) -> impl Strategy<Value = Spanned<Statement>> {
+        prop_oneof![
+            (identifier(), expression.clone())
+                .prop_map(|(name, value)| Statement::Let(name, value).into()),
+            expression
+                .clone()
+                .prop_map(|value| Statement::Return(value).into()),
+            expression.prop_map(|expression| Statement::Expression(expression).into()),
+        ]
+    }
+
+    //any ast the parser could give back - integers are never negative, as "-5" is parsed as a prefix expression
+    pub fn expression() -> impl Strategy<Value = Spanned<Expression>> + Clone {
+        let leaf = prop_oneof![
+            identifier().prop_map(|name| Expression::Identifier(name.node)),
+            (0..=i32::MAX).prop_map(Expression::Integer),
+            any::<bool>().prop_map(Expression::Boolean),
+        ]
+        .prop_map(Spanned::from);
+
+        leaf.prop_recursive(4, 48, 3, |inner| {
+            prop_oneof![
+                (select(vec![Prefix::Bang, Prefix::Minus]), inner.clone()).prop_map(
+                    |(operator, right)| Expression::Prefix(operator, Box::new(right)).into()
+                ),
+                (
+                    select(vec![
+                        Infix::Plus,
+                        Infix::Minus,
+                        Infix::Asterisk,
+                        Infix::Slash,
+                        Infix::Percent,
+                        Infix::LessThan,
+                        Infix::GreaterThan,
+                        Infix::LessThanEqual,
+                        Infix::GreaterThanEqual,
+                        Infix::Equal,
+                        Infix::NotEqual,
+                        Infix::And,
+                        Infix::Or,
+                    ]),
+                    inner.clone(),
+                    inner.clone()
+                )
+                    .prop_map(|(operator, left, right)| {
+                        Expression::Infix(operator, Box::new(left), Box::new(right)).into()
+                    }),
+                (
+                    inner.clone(),
+                    block(inner.clone()),
+                    prop::option::of(block(inner.clone()))
+                )
+                    .prop_map(|(condition, consequence, alternative)| {
+                        Expression::If {
+                            condition: Box::new(condition),
+                            consequence,
+                            alternative,
+                        }
+                        .into()
+                    }),
+                (
+                    prop::collection::vec(identifier(), 0..3),
+                    block(inner.clone())
+                )
+                    .prop_map(|(parameters, body)| Expression::Function {
+                        parameters,
+                        body
+                    }
+                    .into()),
+                (inner.clone(), prop::collection::vec(inner.clone(), 0..3)).prop_map(
+                    |(function, arguments)| Expression::Call {
+                        function: Box::new(function),
+                        arguments,
+                    }
+                    .into()
+                ),
+                (identifier(), inner).prop_map(|(name, value)| Expression::Assign(
+                    name,
+                    Box::new(value)
+                )
+                .into()),
+            ]
+        })
+    }
+
+    pub fn program() -> impl Strategy<Value = Program> {
+        prop::collection::vec(statement(expression()), 0..4)
+            .prop_map(|statements| Program { statements })
+    }
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    #[test]
+    fn test_display_round_trip(program in strategies::program()) {
+        let minimal: String = program.to_string();
+        proptest::prop_assert_eq!(&parse(&minimal), &program, "{}", minimal);
+
+        let bracketed: String = format!("{program:#}");
+        proptest::prop_assert_eq!(&parse(&bracketed), &program, "{}", bracketed);
+    }
 }
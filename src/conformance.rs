@@ -0,0 +1,238 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::ast::Program;
+use crate::environment::Environment;
+use crate::evaluator::eval_program;
+use crate::files::monkey_files;
+use crate::lexer::Lexer;
+use crate::object::Object;
+use crate::parser::Parser;
+
+//a golden-file conformance suite - every .monkey file in a directory is run, and what it did is compared with the files next to it:
+//  name.stdout  everything the program printed
+//  name.value   the value of the program, e.g. "15"
+//  name.error   the errors that stopped it, e.g. "2:5: division by zero"
+//a missing file expects nothing - so a program that runs without errors has no .error file.
+//each way of running monkey is a Backend - there is no compiler or VM yet, so the tree-walking evaluator is the only one, but any added to backends() are run against the same suite and have to agree with it on every program
+pub trait Backend {
+    //what the backend is called in reports, e.g. "evaluator"
+    fn name(&self) -> &'static str;
+
+    //runs the whole of the source as a program, from a fresh environment
+    fn run(&self, source: &str) -> Outcome;
+}
+
+//what running a program did - see Backend::run()
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Outcome {
+    pub stdout: String,
+    //None if the program didn't run to the end
+    pub value: Option<String>,
+    //every parser error, or the runtime error, one per line with where it happened
+    pub error: Option<String>,
+}
+
+//runs programs with the tree-walking evaluator
+pub struct Evaluator;
+
+impl Backend for Evaluator {
+    fn name(&self) -> &'static str {
+        "evaluator"
+    }
+
+    //monkey has no way to print yet, so nothing ends up in stdout
+    fn run(&self, source: &str) -> Outcome {
+        let mut parser: Parser = Parser::new(Lexer::new(source));
+        let program: Program = parser.parse_program();
+        if !parser.errors().is_empty() {
+            return Outcome {
+                error: Some(parser.render_errors(source).join("\n")),
+                ..Outcome::default()
+            };
+        }
+
+        let env: Rc<RefCell<Environment>> = Rc::new(RefCell::new(Environment::new()));
+        match eval_program(&program, &env) {
+            Object::Error(error) => Outcome {
+                error: Some(error.render(source)),
+                ..Outcome::default()
+            },
+            value => Outcome {
+                value: Some(value.to_string()),
+                ..Outcome::default()
+            },
+        }
+    }
+}
+
+//every backend the conformance suite is run against - only the evaluator, until there is another way to run monkey
+pub fn backends() -> Vec<Box<dyn Backend>> {
+    vec![Box::new(Evaluator)]
+}
+
+//a program in the suite that didn't do what was expected of it
+#[derive(Debug)]
+pub struct Failure {
+    pub file: PathBuf,
+    pub backend: &'static str,
+    //the extension of each expected file that didn't match, with a diff of it against what happened
+    pub diffs: Vec<(&'static str, String)>,
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} failed with the {}:",
+            self.file.display(),
+            self.backend
+        )?;
+        for (extension, diff) in &self.diffs {
+            writeln!(f, "  {extension} (- expected, + actual):")?;
+            for line in diff.lines() {
+                writeln!(f, "    {line}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+//runs every .monkey file in the directory (and the directories in it) with the backend, and returns the ones that didn't do what their expected files say
+pub fn run_suite(directory: &Path, backend: &dyn Backend) -> io::Result<Vec<Failure>> {
+    let mut failures: Vec<Failure> = Vec::new();
+    for file in monkey_files(directory)? {
+        let outcome: Outcome = backend.run(&fs::read_to_string(&file)?);
+
+        let mut diffs: Vec<(&'static str, String)> = Vec::new();
+        for (extension, actual) in expectations(&outcome) {
+            let expected: String = match fs::read_to_string(file.with_extension(extension)) {
+                Ok(expected) => expected,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+                Err(err) => return Err(err),
+            };
+            if expected != actual {
+                diffs.push((extension, diff(&expected, &actual)));
+            }
+        }
+
+        if !diffs.is_empty() {
+            failures.push(Failure {
+                file,
+                backend: backend.name(),
+                diffs,
+            });
+        }
+    }
+    Ok(failures)
+}
+
+//writes what each .monkey file in the directory did with the backend to its expected files, removing those that expect nothing - for when the expectations change
+pub fn update_suite(directory: &Path, backend: &dyn Backend) -> io::Result<()> {
+    for file in monkey_files(directory)? {
+        let outcome: Outcome = backend.run(&fs::read_to_string(&file)?);
+        for (extension, actual) in expectations(&outcome) {
+            let path: PathBuf = file.with_extension(extension);
+            if !actual.is_empty() {
+                fs::write(path, actual)?;
+            } else if path.exists() {
+                fs::remove_file(path)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+//the contents each expected file should have for the outcome - empty for a file that shouldn't exist
+fn expectations(outcome: &Outcome) -> [(&'static str, String); 3] {
+    let line = |text: &Option<String>| {
+        text.as_ref()
+            .map_or(String::new(), |text| format!("{text}\n"))
+    };
+    [
+        ("stdout", outcome.stdout.clone()),
+        ("value", line(&outcome.value)),
+        ("error", line(&outcome.error)),
+    ]
+}
+
+//a line by line diff of two texts - lines only in the expected text start with "-", lines only in the actual one with "+", and lines in both with " "
+fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+
+    //common[i][j] is the length of the longest common subsequence of expected[i..] and actual[j..]
+    let mut common: Vec<Vec<usize>> = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut out: String = String::new();
+    let (mut i, mut j): (usize, usize) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            out.push_str(&format!(" {}\n", expected[i]));
+            i += 1;
+            j += 1;
+        } else if j == actual.len() || (i < expected.len() && common[i + 1][j] >= common[i][j + 1])
+        {
+            out.push_str(&format!("-{}\n", expected[i]));
+            i += 1;
+        } else {
+            out.push_str(&format!("+{}\n", actual[j]));
+            j += 1;
+        }
+    }
+    out
+}
+
+#[test]
+fn test_evaluator_outcomes() {
+    let tests: Vec<(&str, Outcome)> = vec![
+        (
+            "let x = 5;\nx * 3",
+            Outcome {
+                value: Some("15".to_string()),
+                ..Outcome::default()
+            },
+        ),
+        (
+            "let x = 5;\nx / 0",
+            Outcome {
+                error: Some("2:1: division by zero".to_string()),
+                ..Outcome::default()
+            },
+        ),
+        (
+            "let = 5;\nlet x 5;",
+            Outcome {
+                error: Some(
+                    "1:5: expected an identifier, got \"=\"\n2:7: expected \"=\", got the integer 5"
+                        .to_string(),
+                ),
+                ..Outcome::default()
+            },
+        ),
+    ];
+
+    tests.into_iter().for_each(|(input, expected)| {
+        assert_eq!(Evaluator.run(input), expected, "{input}");
+    });
+}
+
+#[test]
+fn test_diff() {
+    assert_eq!(diff("a\nb\nc\n", "a\nb\nc\n"), " a\n b\n c\n");
+    assert_eq!(diff("a\nb\nc\n", "a\nx\nc\nd\n"), " a\n-b\n+x\n c\n+d\n");
+    assert_eq!(diff("", "1\n"), "+1\n");
+}
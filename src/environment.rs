@@ -0,0 +1,96 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::object::Object;
+
+//stores the values bound to each name - a function call gets its own environment enclosed by the one the function was made in, so it can see the names outside it.
+//a let in the call only shadows an outer name, but assigning to it (see assign()) changes the outer binding
+#[derive(Debug, Default)]
+pub struct Environment {
+    store: HashMap<String, Object>,
+    outer: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //generates a new environment inside another, e.g. for the body of a function call
+    pub fn new_enclosed(outer: Rc<RefCell<Environment>>) -> Self {
+        Self {
+            store: HashMap::new(),
+            outer: Some(outer),
+        }
+    }
+
+    //looks up a name, checking each outer environment in turn if it isn't bound in this one
+    pub fn get(&self, name: &str) -> Option<Object> {
+        match self.store.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self
+                .outer
+                .as_ref()
+                .and_then(|outer| outer.borrow().get(name)),
+        }
+    }
+
+    //every name that can be looked up from this environment (including in outer ones), in alphabetical order
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = match &self.outer {
+            Some(outer) => outer.borrow().names(),
+            None => Vec::new(),
+        };
+        names.extend(self.store.keys().cloned());
+        names.sort();
+        names.dedup();
+
+        names
+    }
+
+    //changes the value of the nearest binding of the name, checking each outer environment in turn if it isn't bound in this one - returns false if it isn't bound anywhere
+    pub fn assign(&mut self, name: &str, value: Object) -> bool {
+        match self.store.get_mut(name) {
+            Some(binding) => {
+                *binding = value;
+                true
+            }
+            None => self
+                .outer
+                .as_ref()
+                .is_some_and(|outer| outer.borrow_mut().assign(name, value)),
+        }
+    }
+
+    //binds a name in this environment, replacing any value it had here before
+    pub fn set(&mut self, name: &str, value: Object) {
+        self.store.insert(name.to_string(), value);
+    }
+}
+
+#[test]
+fn test_enclosed_environment() {
+    let outer: Rc<RefCell<Environment>> = Rc::new(RefCell::new(Environment::new()));
+    outer.borrow_mut().set("x", Object::Integer(1));
+    outer.borrow_mut().set("y", Object::Integer(2));
+
+    let mut inner: Environment = Environment::new_enclosed(Rc::clone(&outer));
+    inner.set("y", Object::Boolean(true));
+
+    assert_eq!(inner.get("x"), Some(Object::Integer(1)));
+    assert_eq!(inner.get("y"), Some(Object::Boolean(true)));
+    assert_eq!(inner.get("z"), None);
+    assert_eq!(outer.borrow().get("y"), Some(Object::Integer(2)));
+
+    assert!(inner.assign("x", Object::Integer(3)));
+    assert!(inner.assign("y", Object::Integer(4)));
+    assert!(!inner.assign("z", Object::Integer(5)));
+    assert_eq!(outer.borrow().get("x"), Some(Object::Integer(3)));
+    assert_eq!(outer.borrow().get("y"), Some(Object::Integer(2)));
+    assert_eq!(inner.get("y"), Some(Object::Integer(4)));
+    assert_eq!(inner.get("z"), None);
+
+    inner.set("a", Object::Null);
+    assert_eq!(inner.names(), vec!["a", "x", "y"]);
+}
@@ -0,0 +1,557 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::ast::{BlockStatement, Expression, Infix, Prefix, Program, Spanned, Statement};
+use crate::environment::Environment;
+use crate::object::{self, Builtin, Function, Object, RuntimeError};
+
+//inside the evaluator, errors are passed up with ? - they only become an Object::Error once they reach eval_program()
+type EvalResult = Result<Object, RuntimeError>;
+
+//a tree-walking evaluator - runs each statement in a program in order, in the environment given so names bound by one program can be used by the next (e.g. in the repl), and returns the value of the last one
+pub fn eval_program(program: &Program, env: &Rc<RefCell<Environment>>) -> Object {
+    let mut result: Object = Object::Null;
+
+    for statement in &program.statements {
+        match eval_statement(statement, env) {
+            Ok(Object::ReturnValue(value)) => return *value,
+            Ok(value) => result = value,
+            Err(error) => return Object::Error(error),
+        }
+    }
+
+    result
+}
+
+//calls a function (or builtin) with the arguments given, e.g. the tests run by "monkey test" - like eval_program(), an error is returned as an Object::Error
+pub fn call_function(function: Object, arguments: Vec<Object>) -> Object {
+    apply_function(function, arguments).unwrap_or_else(Object::Error)
+}
+
+//unlike eval_program(), a return value is passed up as it is, so it also stops any blocks the block is nested in
+fn eval_block_statement(block: &BlockStatement, env: &Rc<RefCell<Environment>>) -> EvalResult {
+    let mut result: Object = Object::Null;
+
+    for statement in &block.statements {
+        result = eval_statement(statement, env)?;
+        if let Object::ReturnValue(_) = result {
+            break;
+        }
+    }
+
+    Ok(result)
+}
+
+fn eval_statement(statement: &Statement, env: &Rc<RefCell<Environment>>) -> EvalResult {
+    match statement {
+        Statement::Let(name, value) => {
+            let value: Object = eval_expression(value, env)?;
+            env.borrow_mut().set(name, value);
+            Ok(Object::Null)
+        }
+        Statement::Return(value) => {
+            let value: Object = eval_expression(value, env)?;
+            Ok(Object::ReturnValue(Box::new(value)))
+        }
+        Statement::Expression(expression) => eval_expression(expression, env),
+    }
+}
+
+//any error from evaluating the expression that doesn't say where it happened yet is given the expression's span - so errors point at the innermost expression they came from
+fn eval_expression(expression: &Spanned<Expression>, env: &Rc<RefCell<Environment>>) -> EvalResult {
+    eval_expression_node(expression, env).map_err(|error| error.with_span(expression.span))
+}
+
+fn eval_expression_node(expression: &Expression, env: &Rc<RefCell<Environment>>) -> EvalResult {
+    match expression {
+        //builtins are only looked up once no binding of the name is found, so they can be shadowed like any other name
+        Expression::Identifier(name) => env
+            .borrow()
+            .get(name)
+            .or_else(|| Builtin::lookup(name).map(Object::Builtin))
+            .ok_or_else(|| RuntimeError::new(format!("identifier not found: {name}"))),
+        Expression::Integer(value) => Ok(Object::Integer(*value)),
+        Expression::Boolean(value) => Ok(Object::Boolean(*value)),
+        Expression::Prefix(operator, right) => {
+            let right: Object = eval_expression(right, env)?;
+            eval_prefix_expression(*operator, right)
+        }
+        //the right side of "&&" and "||" is only evaluated if the left side doesn't already decide the answer
+        Expression::Infix(Infix::And, left, right) => {
+            if !eval_expression(left, env)?.is_truthy() {
+                return Ok(Object::Boolean(false));
+            }
+            Ok(Object::Boolean(eval_expression(right, env)?.is_truthy()))
+        }
+        Expression::Infix(Infix::Or, left, right) => {
+            if eval_expression(left, env)?.is_truthy() {
+                return Ok(Object::Boolean(true));
+            }
+            Ok(Object::Boolean(eval_expression(right, env)?.is_truthy()))
+        }
+        Expression::Infix(operator, left, right) => {
+            let left: Object = eval_expression(left, env)?;
+            let right: Object = eval_expression(right, env)?;
+            eval_infix_expression(*operator, left, right)
+        }
+        Expression::If {
+            condition,
+            consequence,
+            alternative,
+        } => {
+            if eval_expression(condition, env)?.is_truthy() {
+                eval_block_statement(consequence, env)
+            } else if let Some(alternative) = alternative {
+                eval_block_statement(alternative, env)
+            } else {
+                Ok(Object::Null)
+            }
+        }
+        Expression::Function { parameters, body } => Ok(Object::Function(Rc::new(Function {
+            parameters: parameters
+                .iter()
+                .map(|parameter| parameter.node.clone())
+                .collect(),
+            body: body.node.clone(),
+            env: Rc::clone(env),
+        }))),
+        Expression::Call {
+            function,
+            arguments,
+        } => {
+            let function: Object = eval_expression(function, env)?;
+            let arguments: Vec<Object> = arguments
+                .iter()
+                .map(|argument| eval_expression(argument, env))
+                .collect::<Result<_, _>>()?;
+            apply_function(function, arguments)
+        }
+        //the name is assigned to wherever it is bound, so a closure changes the binding it captured rather than making its own
+        Expression::Assign(name, value) => {
+            let value: Object = eval_expression(value, env)?;
+            if !env.borrow_mut().assign(name, value.clone()) {
+                return Err(
+                    RuntimeError::new(format!("identifier not found: {}", name.node))
+                        .with_span(name.span),
+                );
+            }
+            Ok(value)
+        }
+    }
+}
+
+fn eval_prefix_expression(operator: Prefix, right: Object) -> EvalResult {
+    match (operator, right) {
+        (Prefix::Bang, right) => Ok(Object::Boolean(!right.is_truthy())),
+        (Prefix::Minus, Object::Integer(value)) => object::checked_neg(value).map(Object::Integer),
+        (operator, right) => Err(RuntimeError::new(format!(
+            "unknown operator: {operator}{}",
+            right.type_name()
+        ))),
+    }
+}
+
+//"&&" and "||" are dealt with in eval_expression(), as they can't have both sides evaluated first
+fn eval_infix_expression(operator: Infix, left: Object, right: Object) -> EvalResult {
+    match (&left, &right) {
+        (Object::Integer(left), Object::Integer(right)) => {
+            eval_integer_infix_expression(operator, *left, *right)
+        }
+        (Object::Boolean(left), Object::Boolean(right)) if operator == Infix::Equal => {
+            Ok(Object::Boolean(left == right))
+        }
+        (Object::Boolean(left), Object::Boolean(right)) if operator == Infix::NotEqual => {
+            Ok(Object::Boolean(left != right))
+        }
+        (Object::Null, Object::Null) if operator == Infix::Equal => Ok(Object::Boolean(true)),
+        (Object::Null, Object::Null) if operator == Infix::NotEqual => Ok(Object::Boolean(false)),
+        _ if left.type_name() != right.type_name() => Err(RuntimeError::new(format!(
+            "type mismatch: {} {operator} {}",
+            left.type_name(),
+            right.type_name()
+        ))),
+        _ => Err(RuntimeError::new(format!(
+            "unknown operator: {} {operator} {}",
+            left.type_name(),
+            right.type_name()
+        ))),
+    }
+}
+
+//all integer arithmetic goes through the checked functions in object, so overflow and division by zero give a monkey error rather than a panic
+fn eval_integer_infix_expression(operator: Infix, left: i32, right: i32) -> EvalResult {
+    match operator {
+        Infix::Plus => object::checked_add(left, right).map(Object::Integer),
+        Infix::Minus => object::checked_sub(left, right).map(Object::Integer),
+        Infix::Asterisk => object::checked_mul(left, right).map(Object::Integer),
+        Infix::Slash => object::checked_div(left, right).map(Object::Integer),
+        Infix::Percent => object::checked_rem(left, right).map(Object::Integer),
+        Infix::LessThan => Ok(Object::Boolean(left < right)),
+        Infix::GreaterThan => Ok(Object::Boolean(left > right)),
+        Infix::LessThanEqual => Ok(Object::Boolean(left <= right)),
+        Infix::GreaterThanEqual => Ok(Object::Boolean(left >= right)),
+        Infix::Equal => Ok(Object::Boolean(left == right)),
+        Infix::NotEqual => Ok(Object::Boolean(left != right)),
+        Infix::And | Infix::Or => Err(RuntimeError::new(format!(
+            "unknown operator: INTEGER {operator} INTEGER"
+        ))),
+    }
+}
+
+//calls a function with the arguments given, in a new environment enclosed by the one the function was made in
+fn apply_function(function: Object, arguments: Vec<Object>) -> EvalResult {
+    let function: Rc<Function> = match function {
+        Object::Function(function) => function,
+        Object::Builtin(builtin) => return builtin.call(arguments),
+        other => {
+            return Err(RuntimeError::new(format!(
+                "not a function: {}",
+                other.type_name()
+            )))
+        }
+    };
+
+    if function.parameters.len() != arguments.len() {
+        return Err(RuntimeError::new(format!(
+            "wrong number of arguments: expected {}, got {}",
+            function.parameters.len(),
+            arguments.len()
+        )));
+    }
+
+    let mut env: Environment = Environment::new_enclosed(Rc::clone(&function.env));
+    function
+        .parameters
+        .iter()
+        .zip(arguments)
+        .for_each(|(parameter, argument)| env.set(parameter, argument));
+
+    //a return statement only returns from this function, not the one calling it
+    match eval_block_statement(&function.body, &Rc::new(RefCell::new(env)))? {
+        Object::ReturnValue(value) => Ok(*value),
+        value => Ok(value),
+    }
+}
+
+#[cfg(test)]
+use crate::{lexer::Lexer, parser::Parser, token::Span};
+
+//parses and evaluates the input in a fresh environment, failing the test if it didn't parse
+#[cfg(test)]
+fn test_eval(input: &str) -> Object {
+    let mut parser: Parser = Parser::new(Lexer::new(input));
+    let program: Program = parser.parse_program();
+    assert!(
+        parser.errors().is_empty(),
+        "{input}: parser had errors {:?}",
+        parser.errors()
+    );
+
+    eval_program(&program, &Rc::new(RefCell::new(Environment::new())))
+}
+
+#[test]
+fn test_eval_integer_expressions() {
+    let tests: Vec<(&str, i32)> = vec![
+        ("5", 5),
+        ("-10", -10),
+        ("5 + 5 + 5 + 5 - 10", 10),
+        ("2 * 2 * 2 * 2 * 2", 32),
+        ("-50 + 100 + -50", 0),
+        ("20 + 2 * -10", 0),
+        ("50 / 2 * 2 + 10", 60),
+        ("3 * (3 * 3) + 10", 37),
+        ("(5 + 10 * 2 + 15 / 3) * 2 + -10", 50),
+        ("17 % 5", 2),
+        ("-17 % 5", -2),
+        ("2 + 10 % 4 * 3", 8),
+    ];
+
+    tests.into_iter().for_each(|(input, expected)| {
+        assert_eq!(test_eval(input), Object::Integer(expected), "{input}");
+    });
+}
+
+#[test]
+fn test_eval_boolean_expressions() {
+    let tests: Vec<(&str, bool)> = vec![
+        ("true", true),
+        ("!true", false),
+        ("!!5", true),
+        ("1 < 2", true),
+        ("1 > 2", false),
+        ("1 <= 1", true),
+        ("2 <= 1", false),
+        ("1 >= 1", true),
+        ("1 >= 2", false),
+        ("1 == 1", true),
+        ("1 != 1", false),
+        ("true == false", false),
+        ("(1 < 2) == true", true),
+        ("(1 >= 2) != true", true),
+        ("true && true", true),
+        ("true && false", false),
+        ("false || true", true),
+        ("false || false", false),
+        ("1 < 2 && 2 < 3 || false", true),
+        ("0 && 1", true),
+    ];
+
+    tests.into_iter().for_each(|(input, expected)| {
+        assert_eq!(test_eval(input), Object::Boolean(expected), "{input}");
+    });
+}
+
+#[test]
+fn test_short_circuit_evaluation() {
+    //the right side would be an error if it were evaluated
+    let tests: Vec<(&str, bool)> = vec![
+        ("false && undefined", false),
+        ("true || undefined", true),
+        ("false && 1 / 0 == 1", false),
+        ("let calls = fn() { 1 / 0 }; true || calls()", true),
+        ("let x = 5; x > 10 && x / 0 > 1", false),
+    ];
+
+    tests.into_iter().for_each(|(input, expected)| {
+        assert_eq!(test_eval(input), Object::Boolean(expected), "{input}");
+    });
+
+    //but it is evaluated when it is needed
+    assert_eq!(
+        test_eval("true && undefined"),
+        Object::Error(
+            RuntimeError::new("identifier not found: undefined").with_span(Span::new(8, 17))
+        )
+    );
+    assert_eq!(
+        test_eval("false || 1 / 0"),
+        Object::Error(RuntimeError::new("division by zero").with_span(Span::new(9, 14)))
+    );
+}
+
+#[test]
+fn test_eval_if_else_expressions() {
+    let tests: Vec<(&str, Object)> = vec![
+        ("if (true) { 10 }", Object::Integer(10)),
+        ("if (false) { 10 }", Object::Null),
+        ("if (1) { 10 }", Object::Integer(10)),
+        ("if (1 < 2) { 10 } else { 20 }", Object::Integer(10)),
+        ("if (1 > 2) { 10 } else { 20 }", Object::Integer(20)),
+    ];
+
+    tests.into_iter().for_each(|(input, expected)| {
+        assert_eq!(test_eval(input), expected, "{input}");
+    });
+}
+
+#[test]
+fn test_eval_return_statements() {
+    let tests: Vec<(&str, i32)> = vec![
+        ("return 10;", 10),
+        ("return 10; 9;", 10),
+        ("9; return 2 * 5; 9;", 10),
+        ("if (10 > 1) { if (10 > 1) { return 10; } return 1; }", 10),
+    ];
+
+    tests.into_iter().for_each(|(input, expected)| {
+        assert_eq!(test_eval(input), Object::Integer(expected), "{input}");
+    });
+}
+
+#[test]
+fn test_eval_errors() {
+    //(input, error, the source of the expression the error is from)
+    let tests: Vec<(&str, &str, &str)> = vec![
+        ("5 + true;", "type mismatch: INTEGER + BOOLEAN", "5 + true"),
+        (
+            "5 + true; 5;",
+            "type mismatch: INTEGER + BOOLEAN",
+            "5 + true",
+        ),
+        ("-true", "unknown operator: -BOOLEAN", "-true"),
+        (
+            "true + false;",
+            "unknown operator: BOOLEAN + BOOLEAN",
+            "true + false",
+        ),
+        (
+            "true <= false;",
+            "unknown operator: BOOLEAN <= BOOLEAN",
+            "true <= false",
+        ),
+        (
+            "if (10 > 1) { if (10 > 1) { return true + false; } return 1; }",
+            "unknown operator: BOOLEAN + BOOLEAN",
+            "true + false",
+        ),
+        ("foobar", "identifier not found: foobar", "foobar"),
+        ("5(1)", "not a function: INTEGER", "5(1)"),
+        (
+            "fn(x) { x }(1, 2)",
+            "wrong number of arguments: expected 1, got 2",
+            "fn(x) { x }(1, 2)",
+        ),
+        //an error inside a function is from where it is in the function, rather than where it was called
+        (
+            "let f = fn(x) { x / 0 };\nf(1)",
+            "division by zero",
+            "x / 0",
+        ),
+        (
+            "2147483647 + 1",
+            "integer overflow in 2147483647 + 1",
+            "2147483647 + 1",
+        ),
+        (
+            "-2147483647 - 2",
+            "integer overflow in -2147483647 - 2",
+            "-2147483647 - 2",
+        ),
+        (
+            "65536 * 65536",
+            "integer overflow in 65536 * 65536",
+            "65536 * 65536",
+        ),
+        ("let x = 0; 10 / x", "division by zero", "10 / x"),
+        ("10 % 0", "division by zero", "10 % 0"),
+        (
+            "-(-2147483647 - 1)",
+            "integer overflow in -(-2147483648)",
+            "-(-2147483647 - 1)",
+        ),
+        ("assert(1 > 2)", "assertion failed", "assert(1 > 2)"),
+        (
+            "let check = fn(x) { assert(x, 7) };\ncheck(false)",
+            "assertion failed: 7",
+            "assert(x, 7)",
+        ),
+        (
+            "assert_eq(1 + 1, 3)",
+            "assertion failed: 2 != 3",
+            "assert_eq(1 + 1, 3)",
+        ),
+        (
+            "assert_eq(1, true)",
+            "assertion failed: 1 != true",
+            "assert_eq(1, true)",
+        ),
+        (
+            "assert()",
+            "wrong number of arguments: expected 1 or 2, got 0",
+            "assert()",
+        ),
+        ("y = 1", "identifier not found: y", "y"),
+        (
+            "let f = fn() { let y = 1; }; f(); y = 2",
+            "identifier not found: y",
+            "y",
+        ),
+        ("let x = 1; x = 1 / 0", "division by zero", "1 / 0"),
+        (
+            "assert_eq(1)",
+            "wrong number of arguments: expected 2, got 1",
+            "assert_eq(1)",
+        ),
+    ];
+
+    tests.into_iter().for_each(|(input, message, source)| {
+        let error: RuntimeError = match test_eval(input) {
+            Object::Error(error) => error,
+            result => panic!("{input}: expected an error, got {result}"),
+        };
+        assert_eq!(error.message, message, "{input}");
+        let span: Span = error.span.expect("every error has a span");
+        assert_eq!(&input[span.start..span.end], source, "{input}");
+    });
+}
+
+#[test]
+fn test_builtins() {
+    let tests: Vec<(&str, Object)> = vec![
+        ("assert(true)", Object::Null),
+        ("assert(1 < 2, 5)", Object::Null),
+        ("assert_eq(fn(x) { x * 2 }(3), 6)", Object::Null),
+        ("assert_eq(1 == 1, true)", Object::Null),
+        ("assert", Object::Builtin(Builtin::Assert)),
+        //a binding of a builtin's name hides the builtin
+        (
+            "let assert = fn(x) { x }; assert(false)",
+            Object::Boolean(false),
+        ),
+    ];
+
+    tests.into_iter().for_each(|(input, expected)| {
+        assert_eq!(test_eval(input), expected, "{input}");
+    });
+}
+
+#[test]
+fn test_eval_let_statements() {
+    let tests: Vec<(&str, i32)> = vec![
+        ("let a = 5; a;", 5),
+        ("let a = 5 * 5; a;", 25),
+        ("let a = 5; let b = a; b;", 5),
+        ("let a = 5; let b = a; let c = a + b + 5; c;", 15),
+    ];
+
+    tests.into_iter().for_each(|(input, expected)| {
+        assert_eq!(test_eval(input), Object::Integer(expected), "{input}");
+    });
+}
+
+#[test]
+fn test_eval_functions() {
+    let tests: Vec<(&str, i32)> = vec![
+        ("let identity = fn(x) { x; }; identity(5);", 5),
+        ("let identity = fn(x) { return x; }; identity(5);", 5),
+        ("let double = fn(x) { x * 2; }; double(5);", 10),
+        ("let add = fn(x, y) { x + y; }; add(5 + 5, add(5, 5));", 20),
+        ("fn(x) { x; }(5)", 5),
+        (
+            "let newAdder = fn(x) { fn(y) { x + y } }; let addTwo = newAdder(2); addTwo(2);",
+            4,
+        ),
+        (
+            "let fib = fn(n) { if (n <= 1) { n } else { fib(n - 1) + fib(n - 2) } }; fib(15);",
+            610,
+        ),
+    ];
+
+    tests.into_iter().for_each(|(input, expected)| {
+        assert_eq!(test_eval(input), Object::Integer(expected), "{input}");
+    });
+}
+
+#[test]
+fn test_eval_assignments() {
+    let tests: Vec<(&str, i32)> = vec![
+        ("let x = 1; x = x + 1; x", 2),
+        //an assignment gives the value assigned
+        ("let x = 1; x = 5", 5),
+        ("let a = 1; let b = 2; a = b = 3; a + b", 6),
+        //the nearest binding is the one changed
+        ("let x = 1; let f = fn(x) { x = 10; x }; f(2) + x", 11),
+        ("let x = 1; let f = fn() { x = 10 }; f(); x", 10),
+        ("let x = 1; if (true) { x = 2; }; x", 2),
+        //each closure keeps changing the binding it captured
+        (
+            "let counter = fn() { let n = 0; fn() { n = n + 1 } };
+            let a = counter(); let b = counter();
+            a(); a(); b();
+            a() * 10 + b()",
+            32,
+        ),
+        (
+            "let sum = 0; let i = 0;
+            let loop = fn() { if (i < 5) { i = i + 1; sum = sum + i; loop() } };
+            loop();
+            sum",
+            15,
+        ),
+    ];
+
+    tests.into_iter().for_each(|(input, expected)| {
+        assert_eq!(test_eval(input), Object::Integer(expected), "{input}");
+    });
+}
@@ -0,0 +1,29 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+//every monkey file at the path - the path itself if it is a file, or every .monkey file in it (and the directories in it) if it is a directory, in order of their paths.
+//used by the subcommands that take paths (e.g. "monkey fmt") and by the conformance suite
+pub fn monkey_files(path: &Path) -> io::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut entries: Vec<PathBuf> = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<_>>()?;
+    entries.sort();
+
+    let mut files: Vec<PathBuf> = Vec::new();
+    for entry in entries {
+        if entry.is_dir() {
+            files.extend(monkey_files(&entry)?);
+        } else if entry
+            .extension()
+            .is_some_and(|extension| extension == "monkey")
+        {
+            files.push(entry);
+        }
+    }
+    Ok(files)
+}
@@ -0,0 +1,471 @@
+use crate::ast::Program;
+use crate::lexer::{Lexer, LosslessKind, LosslessLexer, LosslessToken, Trivia};
+use crate::parser::{Parser, ParserError, Precedence};
+use crate::token::{Span, Token};
+
+//how far each level of nesting is indented
+const INDENT: &str = "    ";
+
+//formats monkey source in the one standard style, keeping every comment:
+//- each statement goes on its own line, indented four spaces for each block it is in, with at most one blank line between statements
+//- binary operators have a space either side, prefix operators have none, and commas are followed by a space
+//- opening braces go at the end of the line before, closing braces on their own line (with "} else {" kept together), and empty blocks are "{}"
+//- let and return statements always end with a ";", as does an expression statement unless it is the last in a block (where it is the value of the block) or ends with a "}" and can't be mistaken as carrying on into the next statement
+//source that doesn't parse isn't formatted, and the errors are returned instead, with where each was found
+pub fn format(source: &str) -> Result<String, Vec<(ParserError<'_>, Span)>> {
+    let mut parser: Parser = Parser::new(Lexer::new(source));
+    let _program: Program = parser.parse_program();
+    if !parser.errors().is_empty() {
+        return Err(parser.spanned_errors());
+    }
+
+    let mut formatter: Formatter = Formatter::new(source);
+    formatter.program();
+    Ok(formatter.out)
+}
+
+//walks the tokens of source that is known to parse, following the same grammar as the parser so it knows how each token is used, and writes them back out formatted.
+//trivia is never part of the grammar - comments are written out as they are passed, and whitespace is only used to tell where comments and blank lines were
+struct Formatter<'source> {
+    tokens: Vec<LosslessToken<'source>>,
+    //the index of the next token to read
+    pos: usize,
+    out: String,
+    indent: usize,
+    //nothing has been written on the current line yet, so the next thing written needs indenting first
+    line_start: bool,
+    //a block comment was just written, so the next token needs a space to separate it
+    pending_space: bool,
+    //blank lines are only kept before statements (and comments before them), not inside expressions
+    at_statement_start: bool,
+    //a line comment ended the last line part way through an expression, so this line carries it on and is indented an extra level
+    continued: bool,
+}
+
+impl<'source> Formatter<'source> {
+    fn new(source: &'source str) -> Self {
+        Self {
+            tokens: LosslessLexer::new(source).collect(),
+            pos: 0,
+            out: String::with_capacity(source.len()),
+            indent: 0,
+            line_start: true,
+            pending_space: false,
+            at_statement_start: false,
+            continued: false,
+        }
+    }
+
+    fn program(&mut self) {
+        while self.peek() != Token::EndOfFile {
+            self.statement(false);
+        }
+
+        //any comments after the last statement
+        self.at_statement_start = true;
+        self.trivia();
+        //a file with anything in ends with a line ending
+        self.newline();
+    }
+
+    //formats a statement on its own line - in_block is whether it is inside a block, where the last expression statement doesn't need a ";"
+    fn statement(&mut self, in_block: bool) {
+        self.at_statement_start = true;
+        let newlines: usize = self.trivia();
+        //a block comment on its own line just before the statement (e.g. "/* why */ let x = 5;") stays with it
+        let after_comment: bool = newlines == 0
+            && self.pending_space
+            && self
+                .out
+                .rsplit('\n')
+                .next()
+                .is_some_and(|line| line.trim_start().starts_with("/*"));
+        if !after_comment {
+            self.newline();
+            if newlines > 1 {
+                self.blank_line();
+            }
+        }
+        self.at_statement_start = false;
+
+        match self.peek() {
+            Token::Let => {
+                self.token();
+                self.write_space();
+                self.token();
+                self.write_space();
+                self.token();
+                self.write_space();
+                self.expression(Precedence::Lowest);
+                self.semicolon(true);
+            }
+            Token::Return => {
+                self.token();
+                self.write_space();
+                self.expression(Precedence::Lowest);
+                self.semicolon(true);
+            }
+            _ => {
+                self.expression(Precedence::Lowest);
+                let ends_with_brace: bool = self.out.ends_with('}');
+
+                self.skip_semicolon();
+                let needed: bool = match self.peek() {
+                    Token::RightBrace if in_block => false,
+                    //these could start the next statement or carry on this one, so the ";" is what keeps them apart
+                    Token::LeftParenthesis | Token::Minus => true,
+                    _ => !ends_with_brace,
+                };
+                if needed {
+                    self.write(";");
+                }
+            }
+        }
+    }
+
+    //the ";" at the end of a statement is optional in the source, so it is skipped if there and then written if required
+    fn semicolon(&mut self, required: bool) {
+        self.skip_semicolon();
+        if required {
+            self.write(";");
+        }
+    }
+
+    //the ";" is taken out of the tokens rather than read, so any comments before it are written after it - it stays on the line of the statement it ends
+    fn skip_semicolon(&mut self) {
+        let Some(at) = (self.pos..self.tokens.len())
+            .find(|&at| !matches!(self.tokens[at].kind, LosslessKind::Trivia(_)))
+        else {
+            return;
+        };
+        if self.tokens[at].kind != LosslessKind::Token(Token::Semicolon) {
+            return;
+        }
+        self.tokens.remove(at);
+
+        //a ";" on a line of its own takes the line ending before it too, so no blank line is left behind
+        let line_end: Option<usize> = self.tokens[self.pos..at]
+            .iter()
+            .rposition(|token| token.kind != LosslessKind::Trivia(Trivia::Whitespace));
+        if let Some(offset) = line_end {
+            if self.tokens[self.pos + offset].kind == LosslessKind::Trivia(Trivia::Newline) {
+                self.tokens.remove(self.pos + offset);
+            }
+        }
+    }
+
+    //formats an expression the same way the parser reads it - see Parser::parse_expression()
+    fn expression(&mut self, precedence: Precedence) {
+        self.prefix();
+
+        loop {
+            let peek: Token = self.peek();
+            if precedence >= Precedence::of(peek) {
+                break;
+            }
+
+            if peek == Token::LeftParenthesis {
+                self.call();
+            } else {
+                self.write_space();
+                self.token();
+                self.write_space();
+                self.expression(Precedence::of(peek));
+            }
+        }
+    }
+
+    fn prefix(&mut self) {
+        match self.token().kind {
+            LosslessKind::Token(Token::Bang | Token::Minus) => {
+                self.expression(Precedence::Prefix);
+            }
+            LosslessKind::Token(Token::LeftParenthesis) => {
+                self.expression(Precedence::Lowest);
+                self.token();
+            }
+            LosslessKind::Token(Token::If) => {
+                self.write_space();
+                self.token();
+                self.expression(Precedence::Lowest);
+                self.token();
+                self.write_space();
+                self.block();
+
+                if self.peek() == Token::Else {
+                    self.write_space();
+                    self.token();
+                    self.write_space();
+                    self.block();
+                }
+            }
+            LosslessKind::Token(Token::Function) => {
+                self.token();
+                self.list(|formatter| {
+                    formatter.token();
+                });
+                self.write_space();
+                self.block();
+            }
+            //identifiers and literals are already written as they are
+            _ => {}
+        }
+    }
+
+    //the arguments of a call - the function has already been written
+    fn call(&mut self) {
+        self.token();
+        self.list(|formatter| formatter.expression(Precedence::Lowest));
+    }
+
+    //formats a list separated by ", " up to and including its ")" - the "(" has already been written
+    fn list(&mut self, mut item: impl FnMut(&mut Self)) {
+        while self.peek() != Token::RightParenthesis {
+            item(self);
+            if self.peek() == Token::Comma {
+                self.token();
+                self.write_space();
+            }
+        }
+        self.token();
+    }
+
+    //formats a block with each statement on its own line, indented one level more
+    fn block(&mut self) {
+        self.token();
+        let opened_at: usize = self.out.len();
+
+        self.indent += 1;
+        while self.peek() != Token::RightBrace {
+            self.statement(true);
+        }
+        //comments after the last statement stay inside the block
+        self.at_statement_start = true;
+        self.trivia();
+        self.at_statement_start = false;
+        self.indent -= 1;
+
+        if self.out.len() > opened_at {
+            self.newline();
+        }
+        self.token();
+    }
+
+    //the next token that isn't trivia, without reading it
+    fn peek(&self) -> Token<'source> {
+        self.tokens[self.pos..]
+            .iter()
+            .find_map(|token| match token.kind {
+                LosslessKind::Token(token) => Some(token),
+                LosslessKind::Trivia(_) => None,
+            })
+            .unwrap_or(Token::EndOfFile)
+    }
+
+    //writes out any comments before the next token, then reads the token without writing it
+    fn next(&mut self) -> LosslessToken<'source> {
+        self.trivia();
+        let token: LosslessToken<'source> = self.tokens[self.pos];
+        self.pos += 1;
+        token
+    }
+
+    //reads the next token and writes it as it is
+    fn token(&mut self) -> LosslessToken<'source> {
+        let token: LosslessToken<'source> = self.next();
+        self.write(token.text);
+        token
+    }
+
+    //writes out the comments up to the next token - a comment on the same line as the code before it stays there, and any other goes on its own line.
+    //returns how many line endings there were since the last comment (or the code before), so the caller can keep a blank line before what comes next
+    fn trivia(&mut self) -> usize {
+        let mut newlines: usize = 0;
+
+        while let Some(token) = self.tokens.get(self.pos) {
+            let LosslessKind::Trivia(trivia) = token.kind else {
+                break;
+            };
+            let text: &str = token.text.trim_end();
+            self.pos += 1;
+
+            match trivia {
+                Trivia::Whitespace => {}
+                Trivia::Newline => newlines += 1,
+                Trivia::LineComment | Trivia::BlockComment => {
+                    if newlines == 0 && !self.line_start {
+                        self.write_space();
+                    } else if newlines > 1 && self.at_statement_start {
+                        self.blank_line();
+                    } else {
+                        self.newline();
+                    }
+                    self.write(text);
+
+                    if trivia == Trivia::LineComment {
+                        self.newline();
+                        self.continued = !self.at_statement_start;
+                    } else {
+                        self.pending_space = true;
+                    }
+                    newlines = 0;
+                }
+            }
+        }
+
+        newlines
+    }
+
+    fn write(&mut self, text: &str) {
+        if self.line_start {
+            self.out
+                .push_str(&INDENT.repeat(self.indent + usize::from(self.continued)));
+            self.line_start = false;
+        } else if self.pending_space && !self.out.ends_with(' ') {
+            self.out.push(' ');
+        }
+        self.pending_space = false;
+        self.out.push_str(text);
+    }
+
+    fn write_space(&mut self) {
+        if !self.line_start && !self.out.ends_with(' ') {
+            self.out.push(' ');
+        }
+    }
+
+    fn newline(&mut self) {
+        if !self.line_start {
+            self.out.push('\n');
+            self.line_start = true;
+        }
+        self.pending_space = false;
+        self.continued = false;
+    }
+
+    //ends the line and leaves an empty one - unless at the start of the file or a block, or there already is one
+    fn blank_line(&mut self) {
+        self.newline();
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") && !self.out.ends_with("{\n") {
+            self.out.push('\n');
+        }
+    }
+}
+
+#[test]
+fn test_format() {
+    let tests: Vec<(&str, &str)> = vec![
+        ("", ""),
+        ("// nothing", "// nothing\n"),
+        ("let x=5", "let x = 5;\n"),
+        ("let  x =-a*  (b+c)\nreturn !x ;", "let x = -a * (b + c);\nreturn !x;\n"),
+        (
+            "let add=fn(x,y){x+y;};add(1,2)",
+            "let add = fn(x, y) {\n    x + y\n};\nadd(1, 2);\n",
+        ),
+        (
+            "if(x<y){\nx}\nelse\n{y}",
+            "if (x < y) {\n    x\n} else {\n    y\n}\n",
+        ),
+        ("let f = fn() {};", "let f = fn() {};\n"),
+        (
+            "let f = fn(n) { if (n < 2) { return n; }; f(n - 1) + f(n - 2) };",
+            "let f = fn(n) {\n    if (n < 2) {\n        return n;\n    }\n    f(n - 1) + f(n - 2)\n};\n",
+        ),
+        //the ";" is what stops the next statement being read as a call or subtraction
+        (
+            "if (x) { 1 };\n(y)\nif (x) { 1 }; -y",
+            "if (x) {\n    1\n};\n(y);\nif (x) {\n    1\n};\n-y;\n",
+        ),
+        //without a ";" it already was a call
+        ("if (x) { f }\n(y)", "if (x) {\n    f\n}(y);\n"),
+        //blank lines are kept between statements, but never more than one
+        (
+            "let a = 1;\n\n\n\nlet b = 2;\nlet c = 3;",
+            "let a = 1;\n\nlet b = 2;\nlet c = 3;\n",
+        ),
+    ];
+
+    tests.into_iter().for_each(|(input, expected)| {
+        assert_eq!(format(input).unwrap(), expected, "{input:?}");
+    });
+}
+
+#[test]
+fn test_format_comments() {
+    let input: &str = "// adds two numbers
+let add = fn(x, y) { // the body
+  /* the result */ x + y
+    // after the result
+};
+
+
+/* a block
+   comment */
+add(1, /* two */ 2) // three
+// the end";
+
+    let expected: &str = "// adds two numbers
+let add = fn(x, y) { // the body
+    /* the result */ x + y
+    // after the result
+};
+
+/* a block
+   comment */
+add(1, /* two */ 2); // three
+// the end
+";
+
+    assert_eq!(format(input).unwrap(), expected);
+    assert_eq!(format(expected).unwrap(), expected);
+}
+
+#[test]
+fn test_format_line_comment_in_expression() {
+    let input: &str = "let x = 1 + // one\n2;\nadd(// first\n  x,\n y)";
+    let expected: &str = "let x = 1 + // one\n    2;\nadd( // first\n    x, y);\n";
+
+    assert_eq!(format(input).unwrap(), expected);
+    assert_eq!(format(expected).unwrap(), expected);
+}
+
+#[test]
+fn test_format_comment_before_semicolon() {
+    let tests: Vec<(&str, &str)> = vec![
+        ("x // c\n;", "x; // c\n"),
+        ("x // c\n;\ny", "x; // c\ny;\n"),
+        (
+            "let x = 1 /* one */ ;\nreturn x // x\n  ;",
+            "let x = 1; /* one */\nreturn x; // x\n",
+        ),
+        ("x\n;\n\ny", "x;\n\ny;\n"),
+    ];
+
+    tests.into_iter().for_each(|(input, expected)| {
+        let formatted: String = format(input).unwrap();
+        assert_eq!(formatted, expected, "{input:?}");
+        assert_eq!(format(&formatted).unwrap(), expected, "{input:?}");
+    });
+}
+
+#[test]
+fn test_format_errors() {
+    assert!(format("let = 5;").is_err());
+    assert!(format("x /* unterminated").is_err());
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    //formatting doesn't change what the source means, and formatting it again doesn't change it any more
+    #[test]
+    fn test_format_keeps_meaning(program in crate::ast::strategies::program()) {
+        for source in [program.to_string(), format!("{program:#}")] {
+            let formatted: String = format(&source).unwrap();
+
+            let mut parser: Parser = Parser::new(Lexer::new(&formatted));
+            proptest::prop_assert_eq!(&parser.parse_program(), &program, "{}", formatted);
+            proptest::prop_assert_eq!(&format(&formatted).unwrap(), &formatted);
+        }
+    }
+}
@@ -0,0 +1,347 @@
+use serde_json::{json, Value};
+
+use crate::ast::{BlockStatement, Expression, Program, Spanned, Statement};
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+use crate::token::{Span, Token};
+
+//monkey code as json, for tools written in other languages - so they can use the lexer and parser rather than writing their own.
+//the format is stable - fields may be added in later versions, but none will be removed or change meaning without "version" going up.
+//
+//every span is {"start": 0, "end": 3} - the byte offsets of the source it covers (start inclusive, end exclusive).
+//
+//tokens_json() gives {"version": 1, "tokens": [...], "errors": [...]}, where each token is
+//  {"type": "Let", "text": "let", "span": ...}
+//and identifiers and integers also have their "value", e.g. {"type": "Integer", "text": "5", "value": 5, "span": ...}.
+//the type of a token is one of Illegal, Identifier, Integer, Assign, Plus, Minus, Bang, Asterisk, Slash, Percent, LessThan, GreaterThan,
+//LessThanEqual, GreaterThanEqual, Equal, NotEqual, And, Or, Comma, Semicolon, LeftParenthesis, RightParenthesis, LeftBrace, RightBrace,
+//Function, Let, True, False, If, Else or Return. whitespace and comments aren't tokens, and there is no token for the end of the input.
+//
+//ast_json() gives {"version": 1, "program": {"type": "Program", "statements": [...]}, "errors": [...]}, where every node has its "type" and "span":
+//  {"type": "Let", "name": <Identifier>, "value": <expression>}
+//  {"type": "Return", "value": <expression>}
+//  {"type": "ExpressionStatement", "expression": <expression>}
+//  {"type": "Block", "statements": [<statement>...]}
+//  {"type": "Identifier", "name": "x"}
+//  {"type": "Integer", "value": 5}
+//  {"type": "Boolean", "value": true}
+//  {"type": "Prefix", "operator": "-", "right": <expression>}
+//  {"type": "Infix", "operator": "+", "left": <expression>, "right": <expression>}
+//  {"type": "If", "condition": <expression>, "consequence": <Block>, "alternative": <Block> or null}
+//  {"type": "Function", "parameters": [<Identifier>...], "body": <Block>}
+//  {"type": "Call", "function": <expression>, "arguments": [<expression>...]}
+//  {"type": "Assign", "name": <Identifier>, "value": <expression>}
+//the span of an expression in brackets doesn't include the brackets, and the span of a statement doesn't include its semicolon.
+//
+//errors are {"message": "...", "span": ...} - tokens and programs are still given for source with errors, with what could be made sense of
+pub const VERSION: u32 = 1;
+
+//the tokens the lexer splits the source into, as json
+pub fn tokens_json(source: &str) -> Value {
+    let mut lexer: Lexer = Lexer::new(source);
+    let mut tokens: Vec<Value> = Vec::new();
+    loop {
+        let (token, span): (Token, Span) = lexer.next_spanned_token();
+        if token == Token::EndOfFile {
+            break;
+        }
+
+        let mut json: Value = json!({
+            "type": token_type(token),
+            "text": &source[span.start..span.end],
+            "span": span_json(span),
+        });
+        match token {
+            Token::Identifier(name) => json["value"] = json!(name),
+            Token::Integer(value) => json["value"] = json!(value),
+            _ => {}
+        }
+        tokens.push(json);
+    }
+
+    let errors: Vec<Value> = lexer
+        .errors()
+        .iter()
+        .map(|error| error_json(error.to_string(), error.span()))
+        .collect();
+
+    json!({
+        "version": VERSION,
+        "tokens": tokens,
+        "errors": errors,
+    })
+}
+
+//the ast the parser makes from the source, as json
+pub fn ast_json(source: &str) -> Value {
+    let mut parser: Parser = Parser::new(Lexer::new(source));
+    let program: Program = parser.parse_program();
+
+    let errors: Vec<Value> = parser
+        .errors()
+        .iter()
+        .zip(parser.error_spans())
+        .map(|(error, span)| error_json(error.to_string(), *span))
+        .collect();
+
+    json!({
+        "version": VERSION,
+        "program": {
+            "type": "Program",
+            "statements": program.statements.iter().map(statement_json).collect::<Vec<Value>>(),
+        },
+        "errors": errors,
+    })
+}
+
+fn span_json(span: Span) -> Value {
+    json!({ "start": span.start, "end": span.end })
+}
+
+fn error_json(message: String, span: Span) -> Value {
+    json!({ "message": message, "span": span_json(span) })
+}
+
+fn name_json(name: &Spanned<String>) -> Value {
+    json!({ "type": "Identifier", "span": span_json(name.span), "name": name.node })
+}
+
+fn statement_json(statement: &Spanned<Statement>) -> Value {
+    let span: Value = span_json(statement.span);
+    match &statement.node {
+        Statement::Let(name, value) => json!({
+            "type": "Let",
+            "span": span,
+            "name": name_json(name),
+            "value": expression_json(value),
+        }),
+        Statement::Return(value) => json!({
+            "type": "Return",
+            "span": span,
+            "value": expression_json(value),
+        }),
+        Statement::Expression(expression) => json!({
+            "type": "ExpressionStatement",
+            "span": span,
+            "expression": expression_json(expression),
+        }),
+    }
+}
+
+fn block_json(block: &Spanned<BlockStatement>) -> Value {
+    json!({
+        "type": "Block",
+        "span": span_json(block.span),
+        "statements": block.statements.iter().map(statement_json).collect::<Vec<Value>>(),
+    })
+}
+
+fn expression_json(expression: &Spanned<Expression>) -> Value {
+    let span: Value = span_json(expression.span);
+    match &expression.node {
+        Expression::Identifier(name) => json!({
+            "type": "Identifier",
+            "span": span,
+            "name": name,
+        }),
+        Expression::Integer(value) => json!({
+            "type": "Integer",
+            "span": span,
+            "value": value,
+        }),
+        Expression::Boolean(value) => json!({
+            "type": "Boolean",
+            "span": span,
+            "value": value,
+        }),
+        Expression::Prefix(operator, right) => json!({
+            "type": "Prefix",
+            "span": span,
+            "operator": operator.to_string(),
+            "right": expression_json(right),
+        }),
+        Expression::Infix(operator, left, right) => json!({
+            "type": "Infix",
+            "span": span,
+            "operator": operator.to_string(),
+            "left": expression_json(left),
+            "right": expression_json(right),
+        }),
+        Expression::If {
+            condition,
+            consequence,
+            alternative,
+        } => json!({
+            "type": "If",
+            "span": span,
+            "condition": expression_json(condition),
+            "consequence": block_json(consequence),
+            "alternative": alternative.as_ref().map(block_json),
+        }),
+        Expression::Function { parameters, body } => json!({
+            "type": "Function",
+            "span": span,
+            "parameters": parameters.iter().map(name_json).collect::<Vec<Value>>(),
+            "body": block_json(body),
+        }),
+        Expression::Call {
+            function,
+            arguments,
+        } => json!({
+            "type": "Call",
+            "span": span,
+            "function": expression_json(function),
+            "arguments": arguments.iter().map(expression_json).collect::<Vec<Value>>(),
+        }),
+        Expression::Assign(name, value) => json!({
+            "type": "Assign",
+            "span": span,
+            "name": name_json(name),
+            "value": expression_json(value),
+        }),
+    }
+}
+
+//the name of the token's type in json - these are part of the format, so don't change when tokens are renamed
+fn token_type(token: Token) -> &'static str {
+    match token {
+        Token::Illegal => "Illegal",
+        Token::EndOfFile => "EndOfFile",
+        Token::Identifier(_) => "Identifier",
+        Token::Integer(_) => "Integer",
+        Token::Assign => "Assign",
+        Token::Plus => "Plus",
+        Token::Minus => "Minus",
+        Token::Bang => "Bang",
+        Token::Asterisk => "Asterisk",
+        Token::Slash => "Slash",
+        Token::Percent => "Percent",
+        Token::LessThan => "LessThan",
+        Token::GreaterThan => "GreaterThan",
+        Token::LessThanEqual => "LessThanEqual",
+        Token::GreaterThanEqual => "GreaterThanEqual",
+        Token::Equal => "Equal",
+        Token::NotEqual => "NotEqual",
+        Token::And => "And",
+        Token::Or => "Or",
+        Token::Comma => "Comma",
+        Token::Semicolon => "Semicolon",
+        Token::LeftParenthesis => "LeftParenthesis",
+        Token::RightParenthesis => "RightParenthesis",
+        Token::LeftBrace => "LeftBrace",
+        Token::RightBrace => "RightBrace",
+        Token::Function => "Function",
+        Token::Let => "Let",
+        Token::True => "True",
+        Token::False => "False",
+        Token::If => "If",
+        Token::Else => "Else",
+        Token::Return => "Return",
+    }
+}
+
+#[test]
+fn test_tokens_json() {
+    assert_eq!(
+        tokens_json("let x = 5; // five\n£"),
+        json!({
+            "version": 1,
+            "tokens": [
+                {"type": "Let", "text": "let", "span": {"start": 0, "end": 3}},
+                {"type": "Identifier", "text": "x", "value": "x", "span": {"start": 4, "end": 5}},
+                {"type": "Assign", "text": "=", "span": {"start": 6, "end": 7}},
+                {"type": "Integer", "text": "5", "value": 5, "span": {"start": 8, "end": 9}},
+                {"type": "Semicolon", "text": ";", "span": {"start": 9, "end": 10}},
+                {"type": "Illegal", "text": "£", "span": {"start": 19, "end": 21}},
+            ],
+            "errors": [],
+        })
+    );
+
+    let json: Value = tokens_json("1 /* never closed");
+    assert_eq!(
+        json["errors"],
+        json!([{
+            "message": "unterminated block comment starting at 1:3",
+            "span": {"start": 2, "end": 4},
+        }])
+    );
+}
+
+#[test]
+fn test_ast_json() {
+    let span = |start: usize, end: usize| json!({"start": start, "end": end});
+
+    assert_eq!(
+        ast_json("let f = fn(x) { -x };\nf(2) * 3"),
+        json!({
+            "version": 1,
+            "program": {
+                "type": "Program",
+                "statements": [
+                    {
+                        "type": "Let",
+                        "span": span(0, 20),
+                        "name": {"type": "Identifier", "span": span(4, 5), "name": "f"},
+                        "value": {
+                            "type": "Function",
+                            "span": span(8, 20),
+                            "parameters": [{"type": "Identifier", "span": span(11, 12), "name": "x"}],
+                            "body": {
+                                "type": "Block",
+                                "span": span(14, 20),
+                                "statements": [{
+                                    "type": "ExpressionStatement",
+                                    "span": span(16, 18),
+                                    "expression": {
+                                        "type": "Prefix",
+                                        "span": span(16, 18),
+                                        "operator": "-",
+                                        "right": {"type": "Identifier", "span": span(17, 18), "name": "x"},
+                                    },
+                                }],
+                            },
+                        },
+                    },
+                    {
+                        "type": "ExpressionStatement",
+                        "span": span(22, 30),
+                        "expression": {
+                            "type": "Infix",
+                            "span": span(22, 30),
+                            "operator": "*",
+                            "left": {
+                                "type": "Call",
+                                "span": span(22, 26),
+                                "function": {"type": "Identifier", "span": span(22, 23), "name": "f"},
+                                "arguments": [{"type": "Integer", "span": span(24, 25), "value": 2}],
+                            },
+                            "right": {"type": "Integer", "span": span(29, 30), "value": 3},
+                        },
+                    },
+                ],
+            },
+            "errors": [],
+        })
+    );
+
+    let json: Value = ast_json("if (true) { x } else { y }; let = 5;");
+    assert_eq!(json["program"]["statements"][0]["expression"]["type"], "If");
+    assert_eq!(
+        json["program"]["statements"][0]["expression"]["alternative"]["statements"][0]
+            ["expression"]["name"],
+        "y"
+    );
+    assert_eq!(
+        json["errors"],
+        json!([{"message": "expected an identifier, got \"=\"", "span": span(32, 33)}])
+    );
+
+    //the lexer's errors are reported too, the same as in the tokens
+    let source: &str = "1; /* never closed";
+    assert_eq!(ast_json(source)["errors"], tokens_json(source)["errors"]);
+    assert_eq!(
+        ast_json(source)["errors"],
+        json!([{"message": "unterminated block comment starting at 1:4", "span": span(3, 5)}])
+    );
+}
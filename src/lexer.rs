@@ -1,11 +1,68 @@
 #![allow(dead_code)]
 
-use std::{iter::Peekable, str::CharIndices};
+use std::{
+    collections::VecDeque,
+    fmt,
+    io::{self, Read},
+    iter::Peekable,
+    str::CharIndices,
+};
 
-use crate::token::Token;
+use unicode_xid::UnicodeXID;
+
+use crate::token::{OwnedToken, Span, Token};
 
 //[TODO?] - add proper documentation?
 
+//errors found while tokenising - the lexer still returns a Token::Illegal in their place so the parser can carry on
+#[derive(Debug, PartialEq, Clone)]
+pub enum LexerError {
+    //the span is the opening "/*" of the outermost comment that was never closed, which starts at the 1-indexed line and column given
+    UnterminatedBlockComment(Span, usize, usize),
+    //an integer literal that doesn't fit in an i32 - the span is the whole literal, and the string is its digits
+    IntegerTooLarge(Span, String),
+}
+
+impl LexerError {
+    //where in the input the error happened
+    pub fn span(&self) -> Span {
+        match self {
+            LexerError::UnterminatedBlockComment(span, ..) => *span,
+            LexerError::IntegerTooLarge(span, _) => *span,
+        }
+    }
+
+    //moves the error along by the number of bytes, lines and columns given - for when the input it was found in was part of a larger one. the columns are only added on the first line, as the lines after start at the beginning
+    fn offset_by(self, offset: usize, lines: usize, columns: usize) -> Self {
+        match self {
+            LexerError::UnterminatedBlockComment(span, line, column) => {
+                LexerError::UnterminatedBlockComment(
+                    Span::new(span.start + offset, span.end + offset),
+                    line + lines,
+                    if line == 1 { column + columns } else { column },
+                )
+            }
+            LexerError::IntegerTooLarge(span, literal) => LexerError::IntegerTooLarge(
+                Span::new(span.start + offset, span.end + offset),
+                literal,
+            ),
+        }
+    }
+}
+
+impl fmt::Display for LexerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexerError::UnterminatedBlockComment(_, line, column) => {
+                write!(f, "unterminated block comment starting at {line}:{column}")
+            }
+            LexerError::IntegerTooLarge(_, literal) => {
+                write!(f, "integer literal {literal} is too large")
+            }
+        }
+    }
+}
+
 //a lexer that borrows an input and returns the tokenised version of the input
 pub struct Lexer<'source> {
     //charIndices - we need to iterate over each character in the input and see what index it is, so we make it into a charIndices list.
@@ -14,6 +71,7 @@ pub struct Lexer<'source> {
     //[TODO?] - make a new version of charIndices that is a struct so instead of having to do tok.1, you can do tok.value?
     input: &'source str,
     iter: Peekable<CharIndices<'source>>,
+    errors: Vec<LexerError>,
 }
 
 //so the parser can peek over the lexers list of tokens
@@ -31,6 +89,29 @@ impl<'source> Iterator for Lexer<'source> {
     }
 }
 
+//the tokens of a lexer along with their spans - see Lexer::spanned()
+pub struct SpannedTokens<'source> {
+    lexer: Lexer<'source>,
+}
+
+impl<'source> SpannedTokens<'source> {
+    //every error the lexer has found in the tokens returned so far
+    pub fn errors(&self) -> &[LexerError] {
+        self.lexer.errors()
+    }
+}
+
+impl<'source> Iterator for SpannedTokens<'source> {
+    type Item = (Token<'source>, Span);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.lexer.next_spanned_token() {
+            (Token::EndOfFile, _) => None,
+            spanned => Some(spanned),
+        }
+    }
+}
+
 //all of the functions that a lexer needs to have to tokenise an input
 impl<'source> Lexer<'source> {
     //generates a new lexer with the correct types
@@ -38,9 +119,15 @@ impl<'source> Lexer<'source> {
         Self {
             input,
             iter: input.char_indices().peekable(),
+            errors: Vec::new(),
         }
     }
 
+    //every error found so far - each one was also returned to the caller as a Token::Illegal
+    pub fn errors(&self) -> &[LexerError] {
+        &self.errors
+    }
+
     //returns either the next char, or a None - if its a None, we have iterated the input past the final line so it should return an EOF - the go tutorial does this by checking if its a blank byte, we do it by making each char an Option, and using a peekable chars list.
     //[TODO] doesn't work with UTF8 encoding - fix in future!
     fn read_char(&mut self) -> Option<(usize, char)> {
@@ -59,10 +146,10 @@ impl<'source> Lexer<'source> {
         }
     }
 
-    //checks if the char ahead is a letter - None or bool
-    fn peek_is_letter(&mut self) -> bool {
+    //checks if the char ahead can carry on an identifier - None or bool
+    fn peek_is_identifier_continue(&mut self) -> bool {
         match self.peek_char() {
-            Some(&ch) => is_letter(ch.1),
+            Some(&ch) => is_identifier_continue(ch.1),
             None => false,
         }
     }
@@ -75,14 +162,15 @@ impl<'source> Lexer<'source> {
         }
     }
 
-    //peeks at the char ahead, and if its a letter, adds one to an iterator and skips to the next char - returns a slice of the original input as the output
-    //[TODO] - intergrate index as a return from self.read_char(), so an extra index isn't needed
+    //peeks at the char ahead, and if it can carry on the identifier, skips to the next char - returns a slice of the original input as the output
+    //the end index is moved on by each char's utf8 length, as identifiers can contain chars that take up more than one byte (e.g. "café", "名前")
     fn read_identifier(&mut self, tok: (usize, char)) -> &'source str {
         let startpos = tok.0;
-        let mut index = startpos + 1;
-        while self.peek_is_letter() {
-            index += 1;
-            self.read_char();
+        let mut index = startpos + tok.1.len_utf8();
+        while self.peek_is_identifier_continue() {
+            if let Some((_, ch)) = self.read_char() {
+                index += ch.len_utf8();
+            }
         }
 
         //[TODO?] - add this for potentially impossible edge case of index being out of range?
@@ -94,17 +182,27 @@ impl<'source> Lexer<'source> {
         &self.input[startpos..index]
     }
 
-    //peeks at the char ahead, and if its a number, adds one to an iterator and skips to the next char - returns a slice of the original input as the output
-    //[TODO] - intergrate index as a return from self.read_char(), so an extra index isn't needed
-    fn read_number(&mut self, tok: (usize, char)) -> i32 {
+    //peeks at the char ahead, and if its a number, skips to the next char - returns the integer the digits make up.
+    //the literal ends at the byte offset of the first char that isn't a digit, so it is sliced on a char boundary whatever comes after it.
+    //a literal too large for an i32 is recorded as an error and returned as a Token::Illegal, so the parser can carry on
+    fn read_number(&mut self, tok: (usize, char)) -> Token<'source> {
         let startpos = tok.0;
-        let mut index = startpos + 1;
         while self.peek_is_number() {
-            index += 1;
             self.read_char();
         }
+        let index: usize = self.offset();
 
-        self.input[startpos..index].parse::<i32>().unwrap()
+        let literal: &str = &self.input[startpos..index];
+        match literal.parse::<i32>() {
+            Ok(value) => Token::Integer(value),
+            Err(_) => {
+                self.errors.push(LexerError::IntegerTooLarge(
+                    Span::new(startpos, index),
+                    literal.to_string(),
+                ));
+                Token::Illegal
+            }
+        }
     }
 
     //the lexer should ignore all whitespace, as it shouldn't matter (except in checking for identifers, where it doens't use this function)
@@ -128,10 +226,102 @@ impl<'source> Lexer<'source> {
         }
     }
 
+    //checks if the next two chars in the input are equal to the ones given - the peekable iterator can only see one char ahead, so this looks at the input directly
+    fn peek_two_chars_eq(&mut self, first: char, second: char) -> bool {
+        match self.peek_char() {
+            Some(&(index, ch)) => {
+                ch == first && self.input[index + ch.len_utf8()..].starts_with(second)
+            }
+            None => false,
+        }
+    }
+
+    //the byte offset of the next char to be read - once the whole input has been read, this is its length
+    fn offset(&mut self) -> usize {
+        match self.peek_char() {
+            Some(&(index, _)) => index,
+            None => self.input.len(),
+        }
+    }
+
+    //checks if a line ending ("\n" or "\r\n") is next
+    fn peek_is_newline(&mut self) -> bool {
+        self.peek_char_eq('\n') || self.peek_two_chars_eq('\r', '\n')
+    }
+
+    //skips everything up to (but not including) the end of the line - the "//" has to be next
+    fn skip_line_comment(&mut self) {
+        while self.peek_char().is_some() && !self.peek_is_newline() {
+            self.read_char();
+        }
+    }
+
+    //skips a block comment, including any nested inside it - the "/*" has to be next. if the input ends before every comment is closed, returns an error pointing at the outermost "/*"
+    fn skip_block_comment(&mut self) -> Result<(), LexerError> {
+        let start: usize = self.offset();
+        let mut depth: usize = 0;
+
+        loop {
+            if self.peek_two_chars_eq('/', '*') {
+                self.read_char();
+                self.read_char();
+                depth += 1;
+            } else if self.peek_two_chars_eq('*', '/') {
+                self.read_char();
+                self.read_char();
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(());
+                }
+            } else if self.read_char().is_none() {
+                let span: Span = Span::new(start, start + 2);
+                let (line, column) = span.line_column(self.input);
+                return Err(LexerError::UnterminatedBlockComment(span, line, column));
+            }
+        }
+    }
+
+    //skips all whitespace and comments before the next token, as neither affect how the code runs
+    fn skip_whitespace_and_comments(&mut self) -> Result<(), LexerError> {
+        loop {
+            self.skip_whitespace();
+
+            if self.peek_two_chars_eq('/', '/') {
+                self.skip_line_comment();
+            } else if self.peek_two_chars_eq('/', '*') {
+                self.skip_block_comment()?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    //iterates over the tokens along with the span each was read from - like iterating over the lexer itself, it stops at the end of the input rather than returning a Token::EndOfFile
+    pub fn spanned(self) -> SpannedTokens<'source> {
+        SpannedTokens { lexer: self }
+    }
+
     //returns the next token from the lexer - e.g. "=" => Token::Assign, "five" => Token::Identifier("five")
     pub fn next_token(&mut self) -> Token<'source> {
-        self.skip_whitespace();
+        self.next_spanned_token().0
+    }
+
+    //returns the next token along with the span of the input it was read from - a Token::EndOfFile has an empty span at the end of the input
+    pub fn next_spanned_token(&mut self) -> (Token<'source>, Span) {
+        if let Err(error) = self.skip_whitespace_and_comments() {
+            let span: Span = error.span();
+            self.errors.push(error);
+            return (Token::Illegal, span);
+        }
+
+        let start: usize = self.offset();
+        let tok: Token<'source> = self.read_token();
+
+        (tok, Span::new(start, self.offset()))
+    }
 
+    //reads the token starting at the next char - there mustn't be any whitespace or comments before it
+    fn read_token(&mut self) -> Token<'source> {
         let tok: Option<(usize, char)> = self.read_char();
         // let Some(tok2) = self.read_char();
 
@@ -167,8 +357,40 @@ impl<'source> Lexer<'source> {
             }
             Some((_, '*')) => Token::Asterisk,
             Some((_, '/')) => Token::Slash,
-            Some((_, '<')) => Token::LessThan,
-            Some((_, '>')) => Token::GreaterThan,
+            Some((_, '%')) => Token::Percent,
+            Some((_, '<')) => {
+                if self.peek_char_eq('=') {
+                    self.read_char();
+                    Token::LessThanEqual
+                } else {
+                    Token::LessThan
+                }
+            }
+            Some((_, '>')) => {
+                if self.peek_char_eq('=') {
+                    self.read_char();
+                    Token::GreaterThanEqual
+                } else {
+                    Token::GreaterThan
+                }
+            }
+            //a single "&" or "|" isn't an operator in monkey
+            Some((_, '&')) => {
+                if self.peek_char_eq('&') {
+                    self.read_char();
+                    Token::And
+                } else {
+                    Token::Illegal
+                }
+            }
+            Some((_, '|')) => {
+                if self.peek_char_eq('|') {
+                    self.read_char();
+                    Token::Or
+                } else {
+                    Token::Illegal
+                }
+            }
             Some((_, ',')) => Token::Comma,
             Some((_, ';')) => Token::Semicolon,
             Some((_, '(')) => Token::LeftParenthesis,
@@ -180,11 +402,11 @@ impl<'source> Lexer<'source> {
             //catches all other options - must be an integer or an identifier - else, its an illegal token.
             //[TODO?] - make read_identifier and read_number take the first part of the Some tuple (the index) as an input instead of having to lend the whole tok variable
             Some((_, ch)) => {
-                if is_letter(ch) {
+                if is_identifier_start(ch) {
                     let literal: &str = self.read_identifier(tok.unwrap());
                     crate::token::lookup_ident(literal)
                 } else if is_number(ch) {
-                    Token::Integer(self.read_number(tok.unwrap()))
+                    self.read_number(tok.unwrap())
                 } else {
                     Token::Illegal
                 }
@@ -194,16 +416,376 @@ impl<'source> Lexer<'source> {
     }
 }
 
-//checks if a char is an accepted identifier character - edit this function to change what can be in an identifer
-fn is_letter(ch: char) -> bool {
-    //[TODO] add other valid identifier chars (e.g. numbers that aren't the first char)
-    ch.is_alphabetic() || ch == '_'
+//a part of the input that doesn't change what the code does, so the normal lexer skips over it
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Trivia {
+    Whitespace,   //a run of whitespace on a single line, e.g. "  \t"
+    Newline,      //"\n" or "\r\n"
+    LineComment,  //"// ..." - not including the line ending
+    BlockComment, //"/* ... */", including any nested comments - runs to the end of the input if it is never closed
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum LosslessKind<'source> {
+    Token(Token<'source>),
+    Trivia(Trivia),
+}
+
+//a token from the lossless lexer, with the exact text it was read from
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct LosslessToken<'source> {
+    pub kind: LosslessKind<'source>,
+    pub text: &'source str,
+    pub span: Span,
+}
+
+//a lexer for tools like formatters that need all of the input - as well as tokens, it returns whitespace, newlines and comments as trivia, so joining the text of every token it returns gives back the input exactly
+pub struct LosslessLexer<'source> {
+    lexer: Lexer<'source>,
+}
+
+impl<'source> LosslessLexer<'source> {
+    pub fn new(input: &'source str) -> Self {
+        Self {
+            lexer: Lexer::new(input),
+        }
+    }
+
+    //the same errors as the normal lexer finds, though an unterminated block comment is returned as Trivia::BlockComment rather than Token::Illegal
+    pub fn errors(&self) -> &[LexerError] {
+        self.lexer.errors()
+    }
+
+    //reads a run of whitespace up to the next line ending, so that newlines are always their own token
+    fn skip_inline_whitespace(&mut self) {
+        while let Some(&(_, ch)) = self.lexer.peek_char() {
+            if !ch.is_whitespace() || self.lexer.peek_is_newline() {
+                break;
+            }
+            self.lexer.read_char();
+        }
+    }
+}
+
+//ends at the end of the input, without returning a Token::EndOfFile
+impl<'source> Iterator for LosslessLexer<'source> {
+    type Item = LosslessToken<'source>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start: usize = self.lexer.offset();
+        let &(_, ch) = self.lexer.peek_char()?;
+
+        let kind: LosslessKind<'source> = if self.lexer.peek_is_newline() {
+            if ch == '\r' {
+                self.lexer.read_char();
+            }
+            self.lexer.read_char();
+            LosslessKind::Trivia(Trivia::Newline)
+        } else if ch.is_whitespace() {
+            self.skip_inline_whitespace();
+            LosslessKind::Trivia(Trivia::Whitespace)
+        } else if self.lexer.peek_two_chars_eq('/', '/') {
+            self.lexer.skip_line_comment();
+            LosslessKind::Trivia(Trivia::LineComment)
+        } else if self.lexer.peek_two_chars_eq('/', '*') {
+            if let Err(error) = self.lexer.skip_block_comment() {
+                self.lexer.errors.push(error);
+            }
+            LosslessKind::Trivia(Trivia::BlockComment)
+        } else {
+            LosslessKind::Token(self.lexer.read_token())
+        };
+
+        let end: usize = self.lexer.offset();
+        Some(LosslessToken {
+            kind,
+            text: &self.lexer.input[start..end],
+            span: Span::new(start, end),
+        })
+    }
+}
+
+//how many bytes the streaming lexer reads from its reader at a time
+const CHUNK_SIZE: usize = 8 * 1024;
+
+//a lexer that reads its input from a reader a chunk at a time as it goes (e.g. a large file, or piped input), rather than needing all of it up front - it returns owned tokens, as the input they came from doesn't stay around
+//it returns the same tokens (with the same spans) as Lexer::new() would for the whole input
+pub struct StreamingLexer<R> {
+    reader: R,
+    //the text that has been read but not lexed yet - at most a chunk, plus a token that was cut off at the end of the one before
+    buffer: String,
+    //the bytes at the end of the last chunk that don't make up a whole char yet - the rest of it comes in the next chunk
+    partial: Vec<u8>,
+    //how many bytes of the input came before the buffer, so spans are from the start of the whole input
+    offset: usize,
+    //how many lines of the input came before the buffer, and how many chars of its first line, so errors give the line and column from the start of the whole input
+    lines: usize,
+    column: usize,
+    //the comment the buffer starts part way through - its text is dropped as it is read rather than kept in the buffer
+    comment: Option<OpenComment>,
+    tokens: VecDeque<(OwnedToken, Span)>,
+    errors: Vec<LexerError>,
+    finished: bool,
+}
+
+//a comment that was still going at the end of the text lexed so far
+enum OpenComment {
+    Line,
+    //how many block comments are open, and the error to give if the input ends before they are all closed
+    Block(usize, LexerError),
+}
+
+impl<R: Read> StreamingLexer<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: String::new(),
+            partial: Vec::new(),
+            offset: 0,
+            lines: 0,
+            column: 0,
+            comment: None,
+            tokens: VecDeque::new(),
+            errors: Vec::new(),
+            finished: false,
+        }
+    }
+
+    //every error found so far - each one was also returned to the caller as a Token::Illegal
+    pub fn errors(&self) -> &[LexerError] {
+        &self.errors
+    }
+
+    //returns the next token - an OwnedToken::Other(Token::EndOfFile) once the reader has run out
+    pub fn next_token(&mut self) -> io::Result<OwnedToken> {
+        Ok(self.next_spanned_token()?.0)
+    }
+
+    //returns the next token along with the span of the input it was read from
+    pub fn next_spanned_token(&mut self) -> io::Result<(OwnedToken, Span)> {
+        while self.tokens.is_empty() && !self.finished {
+            self.read_tokens()?;
+        }
+
+        Ok(self.tokens.pop_front().unwrap_or((
+            OwnedToken::Other(Token::EndOfFile),
+            Span::new(self.offset, self.offset),
+        )))
+    }
+
+    //reads the next chunk and lexes as much of the buffer as can be without the rest of the input.
+    //the last token in the buffer might carry on into the next chunk, so it is kept back to be lexed again with it - unless it is a comment, which is dropped as it is read, keeping only what is needed to find where it ends
+    fn read_tokens(&mut self) -> io::Result<()> {
+        let at_end: bool = self.read_chunk()?;
+
+        self.skip_open_comment();
+        match self.comment.take() {
+            Some(OpenComment::Block(_, error)) if at_end => {
+                self.tokens
+                    .push_back((OwnedToken::Other(Token::Illegal), error.span()));
+                self.errors.push(error);
+                self.drop_text(self.buffer.len());
+                self.finished = true;
+                return Ok(());
+            }
+            Some(comment) if !at_end => {
+                self.comment = Some(comment);
+                return Ok(());
+            }
+            _ => {}
+        }
+
+        let mut lexer: LosslessLexer = LosslessLexer::new(&self.buffer);
+        let items: Vec<LosslessToken> = lexer.by_ref().collect();
+        //the error for a block comment starting at the offset, if it is never closed
+        let unterminated = |start: usize| {
+            lexer.errors().iter().find(|error| {
+                matches!(error, LexerError::UnterminatedBlockComment(span, ..) if span.start == start)
+            })
+        };
+
+        //everything before cut is lexed now, and the text before end is dropped - anything after it is either kept for the next chunk, or is the rest of a comment left open
+        let (cut, end, comment): (usize, usize, Option<OpenComment>) = match items.last() {
+            _ if at_end => (self.buffer.len(), self.buffer.len(), None),
+            Some(item) => {
+                let start: usize = item.span.start;
+                match (item.kind, unterminated(start)) {
+                    (LosslessKind::Token(_), _) => (start, start, None),
+                    (LosslessKind::Trivia(Trivia::LineComment), _) => {
+                        (start, self.buffer.len(), Some(OpenComment::Line))
+                    }
+                    (LosslessKind::Trivia(Trivia::BlockComment), Some(error)) => {
+                        let (depth, scanned) = scan_block_comment(&self.buffer[start..], 0);
+                        let error: LexerError =
+                            error
+                                .clone()
+                                .offset_by(self.offset, self.lines, self.column);
+                        (
+                            start,
+                            start + scanned,
+                            Some(OpenComment::Block(depth, error)),
+                        )
+                    }
+                    (LosslessKind::Trivia(_), _) => (self.buffer.len(), self.buffer.len(), None),
+                }
+            }
+            None => (0, 0, None),
+        };
+
+        let offset: usize = self.offset;
+        self.tokens.extend(
+            items
+                .iter()
+                .filter(|item| item.span.start < cut)
+                .filter_map(|item| match item.kind {
+                    LosslessKind::Token(token) => Some((token.into(), item.span)),
+                    //the normal lexer returns a comment that is never closed as a Token::Illegal at its "/*"
+                    LosslessKind::Trivia(Trivia::BlockComment)
+                        if unterminated(item.span.start).is_some() =>
+                    {
+                        Some((
+                            OwnedToken::Other(Token::Illegal),
+                            Span::new(item.span.start, item.span.start + 2),
+                        ))
+                    }
+                    LosslessKind::Trivia(_) => None,
+                })
+                .map(|(token, span)| (token, Span::new(span.start + offset, span.end + offset))),
+        );
+        let (lines, column): (usize, usize) = (self.lines, self.column);
+        self.errors.extend(
+            lexer
+                .errors()
+                .iter()
+                .filter(|error| error.span().start < cut)
+                .map(|error| error.clone().offset_by(offset, lines, column)),
+        );
+
+        self.drop_text(end);
+        self.comment = comment;
+        self.finished = at_end;
+        Ok(())
+    }
+
+    //reads the next chunk into the buffer - returns true if the reader has run out instead
+    fn read_chunk(&mut self) -> io::Result<bool> {
+        let mut chunk: [u8; CHUNK_SIZE] = [0; CHUNK_SIZE];
+        let read: usize = loop {
+            match self.reader.read(&mut chunk) {
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                result => break result?,
+            }
+        };
+
+        if read == 0 {
+            return match self.partial.is_empty() {
+                true => Ok(true),
+                false => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "the input ends part way through a char",
+                )),
+            };
+        }
+
+        self.partial.extend_from_slice(&chunk[..read]);
+        let valid: usize = match std::str::from_utf8(&self.partial) {
+            Ok(text) => text.len(),
+            //a char cut off at the end of the chunk is finished by the next one - anything else isn't utf-8
+            Err(err) if err.error_len().is_none() => err.valid_up_to(),
+            Err(err) => return Err(io::Error::new(io::ErrorKind::InvalidData, err)),
+        };
+        let text: &str = std::str::from_utf8(&self.partial[..valid]).expect("checked above");
+        self.buffer.push_str(text);
+        self.partial.drain(..valid);
+        Ok(false)
+    }
+
+    //drops as much of the comment the buffer starts in as it can - all of it if the end of the comment has been read
+    fn skip_open_comment(&mut self) {
+        match self.comment.take() {
+            Some(OpenComment::Line) => match self.buffer.find('\n') {
+                Some(end) => self.drop_text(end),
+                None => {
+                    self.drop_text(self.buffer.len());
+                    self.comment = Some(OpenComment::Line);
+                }
+            },
+            Some(OpenComment::Block(depth, error)) => {
+                let (depth, end) = scan_block_comment(&self.buffer, depth);
+                self.drop_text(end);
+                if depth > 0 {
+                    self.comment = Some(OpenComment::Block(depth, error));
+                }
+            }
+            None => {}
+        }
+    }
+
+    //drops the text before end from the buffer, moving where the buffer starts in the input along past it
+    fn drop_text(&mut self, end: usize) {
+        let dropped: &str = &self.buffer[..end];
+        self.offset += end;
+        self.lines += dropped.matches('\n').count();
+        match dropped.rfind('\n') {
+            Some(newline) => self.column = dropped[newline + 1..].chars().count(),
+            None => self.column += dropped.chars().count(),
+        }
+        self.buffer.drain(..end);
+    }
+}
+
+//scans through block comments the same way as Lexer::skip_block_comment(), given how many were open before the text - returns how many are still open after it, and how much of the text was scanned.
+//that is up to just after the "*/" that closes the last of them, or otherwise all of the text apart from a "/" or "*" at the end that could start a "/*" or "*/" with the text after it.
+//"/*" and "*/" are ascii, so the text can be scanned a byte at a time - no byte of a longer char can be mistaken for them
+fn scan_block_comment(text: &str, mut depth: usize) -> (usize, usize) {
+    let bytes: &[u8] = text.as_bytes();
+    let mut index: usize = 0;
+    while index < bytes.len() {
+        if bytes[index..].starts_with(b"/*") {
+            depth += 1;
+            index += 2;
+        } else if bytes[index..].starts_with(b"*/") {
+            depth -= 1;
+            index += 2;
+            if depth == 0 {
+                break;
+            }
+        } else if index + 1 == bytes.len() && matches!(bytes[index], b'/' | b'*') {
+            break;
+        } else {
+            index += 1;
+        }
+    }
+    (depth, index)
+}
+
+//ends at the end of the input, without returning a Token::EndOfFile
+impl<R: Read> Iterator for StreamingLexer<R> {
+    type Item = io::Result<OwnedToken>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.next_token() {
+            Ok(OwnedToken::Other(Token::EndOfFile)) => None,
+            result => Some(result),
+        }
+    }
+}
+
+//checks if a char can start an identifier - follows unicode's XID_Start (plus "_", like rust), so it can't be a digit
+pub(crate) fn is_identifier_start(ch: char) -> bool {
+    ch == '_' || UnicodeXID::is_xid_start(ch)
 }
 
-//checks if a char is an accepted integer character - edit this function to change what can be in an integer
+//checks if a char can carry on an identifier after its first char - follows unicode's XID_Continue, which includes digits and "_"
+pub(crate) fn is_identifier_continue(ch: char) -> bool {
+    UnicodeXID::is_xid_continue(ch)
+}
+
+//checks if a char is an accepted integer character - edit this function to change what can be in an integer.
+//only ascii digits are, as they are all i32::from_str() accepts - digits from other scripts (e.g. "٣", "１") are illegal on their own
 fn is_number(ch: char) -> bool {
     //[TODO] add hex, oct, ect
-    ch.is_numeric()
+    ch.is_ascii_digit()
 }
 
 //[TODO] add more lexer tests
@@ -218,7 +800,7 @@ fn test_next_token() {
         };
         
         let result = add(five, ten);
-        !-/*5;
+        !-/ *5;
         5 < 10 > 5;
         
         if (5 < 10) {
@@ -313,6 +895,379 @@ fn test_next_token() {
     });
 }
 
+#[test]
+fn test_comparison_and_logical_operators() {
+    let input: &str = "a <= b >= c < d > e;
+        x && y || !z;
+        10 % 3;
+        & |";
+
+    let tests: Vec<Token> = vec![
+        Token::Identifier("a"),
+        Token::LessThanEqual,
+        Token::Identifier("b"),
+        Token::GreaterThanEqual,
+        Token::Identifier("c"),
+        Token::LessThan,
+        Token::Identifier("d"),
+        Token::GreaterThan,
+        Token::Identifier("e"),
+        Token::Semicolon,
+        Token::Identifier("x"),
+        Token::And,
+        Token::Identifier("y"),
+        Token::Or,
+        Token::Bang,
+        Token::Identifier("z"),
+        Token::Semicolon,
+        Token::Integer(10),
+        Token::Percent,
+        Token::Integer(3),
+        Token::Semicolon,
+        Token::Illegal,
+        Token::Illegal,
+        Token::EndOfFile,
+    ];
+
+    let mut lex: Lexer = Lexer::new(input);
+    tests.into_iter().for_each(|test| {
+        let token: Token = lex.next_token();
+        assert_eq!(token, test);
+    });
+}
+
+#[test]
+fn test_comments() {
+    let input: &str = "// a line comment
+        let x = 5; // trailing comment
+        /* a block
+           comment */ let y = /* inline */ 10;
+        /* nested /* block */ comments */
+        x / y * 2;
+        //";
+
+    let tests: Vec<Token> = vec![
+        Token::Let,
+        Token::Identifier("x"),
+        Token::Assign,
+        Token::Integer(5),
+        Token::Semicolon,
+        Token::Let,
+        Token::Identifier("y"),
+        Token::Assign,
+        Token::Integer(10),
+        Token::Semicolon,
+        Token::Identifier("x"),
+        Token::Slash,
+        Token::Identifier("y"),
+        Token::Asterisk,
+        Token::Integer(2),
+        Token::Semicolon,
+        Token::EndOfFile,
+    ];
+
+    let mut lex: Lexer = Lexer::new(input);
+    tests.into_iter().for_each(|test| {
+        let token: Token = lex.next_token();
+        assert_eq!(token, test);
+    });
+    assert!(lex.errors().is_empty());
+}
+
+#[test]
+fn test_unterminated_block_comment() {
+    let input: &str = "let x = 5; /* outer /* inner */ still open";
+
+    let mut lex: Lexer = Lexer::new(input);
+    let tokens: Vec<Token> = lex.by_ref().collect();
+
+    assert_eq!(
+        tokens,
+        vec![
+            Token::Let,
+            Token::Identifier("x"),
+            Token::Assign,
+            Token::Integer(5),
+            Token::Semicolon,
+            Token::Illegal,
+        ]
+    );
+    assert_eq!(
+        lex.errors(),
+        &[LexerError::UnterminatedBlockComment(
+            Span::new(11, 13),
+            1,
+            12
+        )]
+    );
+    assert_eq!(
+        lex.errors()[0].to_string(),
+        "unterminated block comment starting at 1:12"
+    );
+}
+
+#[test]
+fn test_integer_too_large() {
+    let input: &str = "let x = 2147483647;\nlet y = 2147483648;";
+
+    let mut lex: Lexer = Lexer::new(input);
+    let tokens: Vec<Token> = lex.by_ref().collect();
+
+    assert_eq!(tokens[3], Token::Integer(i32::MAX));
+    assert_eq!(tokens[8], Token::Illegal);
+    assert_eq!(
+        lex.errors(),
+        &[LexerError::IntegerTooLarge(
+            Span::new(28, 38),
+            String::from("2147483648")
+        )]
+    );
+    assert_eq!(
+        lex.errors()[0].to_string(),
+        "integer literal 2147483648 is too large"
+    );
+}
+
+#[test]
+fn test_identifiers() {
+    let input: &str = "let value2 = x1 + _tmp_3;
+        let café = 名前 * données;
+        let mixed名前2 = αβγ_δ;
+        2x ٣ １２ x٣ 4٣";
+
+    let tests: Vec<Token> = vec![
+        Token::Let,
+        Token::Identifier("value2"),
+        Token::Assign,
+        Token::Identifier("x1"),
+        Token::Plus,
+        Token::Identifier("_tmp_3"),
+        Token::Semicolon,
+        Token::Let,
+        Token::Identifier("café"),
+        Token::Assign,
+        Token::Identifier("名前"),
+        Token::Asterisk,
+        Token::Identifier("données"),
+        Token::Semicolon,
+        Token::Let,
+        Token::Identifier("mixed名前2"),
+        Token::Assign,
+        Token::Identifier("αβγ_δ"),
+        Token::Semicolon,
+        //identifiers still can't start with a digit
+        Token::Integer(2),
+        Token::Identifier("x"),
+        //digits from other scripts can carry on an identifier, but can't be an integer
+        Token::Illegal,
+        Token::Illegal,
+        Token::Illegal,
+        Token::Identifier("x٣"),
+        Token::Integer(4),
+        Token::Illegal,
+        Token::EndOfFile,
+    ];
+
+    let mut lex: Lexer = Lexer::new(input);
+    tests.into_iter().for_each(|test| {
+        let token: Token = lex.next_token();
+        assert_eq!(token, test);
+    });
+}
+
+#[test]
+fn test_identifier_chars() {
+    assert!(is_identifier_start('a'));
+    assert!(is_identifier_start('_'));
+    assert!(is_identifier_start('é'));
+    assert!(is_identifier_start('名'));
+    assert!(!is_identifier_start('1'));
+    assert!(!is_identifier_start('£'));
+    assert!(!is_identifier_start('٣'));
+
+    assert!(is_identifier_continue('1'));
+    assert!(is_identifier_continue('_'));
+    assert!(!is_identifier_continue('-'));
+    assert!(!is_identifier_continue(' '));
+}
+
+#[test]
+fn test_spanned_tokens() {
+    let input: &str = "let café = 10; /* x */ y";
+
+    let mut lex: Lexer = Lexer::new(input);
+    let tests: Vec<(Token, Span)> = vec![
+        (Token::Let, Span::new(0, 3)),
+        (Token::Identifier("café"), Span::new(4, 9)),
+        (Token::Assign, Span::new(10, 11)),
+        (Token::Integer(10), Span::new(12, 14)),
+        (Token::Semicolon, Span::new(14, 15)),
+        (Token::Identifier("y"), Span::new(24, 25)),
+        (Token::EndOfFile, Span::new(25, 25)),
+    ];
+
+    tests.into_iter().for_each(|test| {
+        assert_eq!(lex.next_spanned_token(), test);
+    });
+}
+
+#[test]
+fn test_lossless_tokens() {
+    let input: &str = "let x = 5; // five\r\n\t/* a /* nested */ comment */ x";
+
+    let tests: Vec<(LosslessKind, &str)> = vec![
+        (LosslessKind::Token(Token::Let), "let"),
+        (LosslessKind::Trivia(Trivia::Whitespace), " "),
+        (LosslessKind::Token(Token::Identifier("x")), "x"),
+        (LosslessKind::Trivia(Trivia::Whitespace), " "),
+        (LosslessKind::Token(Token::Assign), "="),
+        (LosslessKind::Trivia(Trivia::Whitespace), " "),
+        (LosslessKind::Token(Token::Integer(5)), "5"),
+        (LosslessKind::Token(Token::Semicolon), ";"),
+        (LosslessKind::Trivia(Trivia::Whitespace), " "),
+        (LosslessKind::Trivia(Trivia::LineComment), "// five"),
+        (LosslessKind::Trivia(Trivia::Newline), "\r\n"),
+        (LosslessKind::Trivia(Trivia::Whitespace), "\t"),
+        (
+            LosslessKind::Trivia(Trivia::BlockComment),
+            "/* a /* nested */ comment */",
+        ),
+        (LosslessKind::Trivia(Trivia::Whitespace), " "),
+        (LosslessKind::Token(Token::Identifier("x")), "x"),
+    ];
+
+    let tokens: Vec<(LosslessKind, &str)> = LosslessLexer::new(input)
+        .map(|token| {
+            assert_eq!(&input[token.span.start..token.span.end], token.text);
+            (token.kind, token.text)
+        })
+        .collect();
+    assert_eq!(tokens, tests);
+}
+
+#[test]
+fn test_lossless_round_trip() {
+    let inputs: Vec<&str> = vec![
+        "",
+        "let five = 5;\n\nlet add = fn(x, y) {\n  x + y // sum\n};\n",
+        "  \t\r\n\n \r  ",
+        "名前 £ @ & | 2x /* unterminated /* comment */",
+        "//only a comment",
+        "a/b/*c*/d//e\r\nf",
+    ];
+
+    inputs.into_iter().for_each(|input| {
+        let mut lex: LosslessLexer = LosslessLexer::new(input);
+        let output: String = lex.by_ref().map(|token| token.text).collect();
+        assert_eq!(output, input);
+
+        //the tokens that aren't trivia are the same as the normal lexer gives
+        let tokens: Vec<Token> = LosslessLexer::new(input)
+            .filter_map(|token| match token.kind {
+                LosslessKind::Token(token) => Some(token),
+                LosslessKind::Trivia(_) => None,
+            })
+            .collect();
+        let mut normal_lex: Lexer = Lexer::new(input);
+        let mut expected: Vec<Token> = normal_lex.by_ref().collect();
+        //apart from an unterminated block comment, which the normal lexer returns as a Token::Illegal
+        if !normal_lex.errors().is_empty() {
+            assert_eq!(expected.pop(), Some(Token::Illegal));
+        }
+        assert_eq!(lex.errors(), normal_lex.errors());
+        assert_eq!(tokens, expected, "{input:?}");
+    });
+}
+
+//a reader that only gives back a few bytes at a time
+#[cfg(test)]
+struct Trickle<'input>(&'input [u8], usize);
+
+#[cfg(test)]
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read: usize = self.1.min(buf.len()).min(self.0.len());
+        buf[..read].copy_from_slice(&self.0[..read]);
+        self.0 = &self.0[read..];
+        Ok(read)
+    }
+}
+
+#[test]
+fn test_streaming_lexer() {
+    let inputs: Vec<&str> = vec![
+        "",
+        "let five = 5;\nlet add = fn(x, y) {\n  x + y // sum\n};\r\nadd(five, 名前);",
+        "let x = /* a comment\nover /* nested\n */ lines */ 10;\n\n\nx",
+        "no newline at the end £",
+        "x /* unterminated\n/* comment */\ny\n",
+        "x\n\n  y /* unterminated on line 3\n",
+        "let x = 1; /* a\nlong /* nested */\n*/ x /* and another\n*/ y",
+        "a /* b */ c /* d\n /* e */\n f",
+        "é /* ü\n",
+    ];
+
+    inputs.into_iter().for_each(|input| {
+        let mut lex: Lexer = Lexer::new(input);
+        //reading a byte at a time makes sure tokens still come out right when they (or the chars in them) are split between chunks
+        let mut stream: StreamingLexer<Trickle> = StreamingLexer::new(Trickle(input.as_bytes(), 1));
+
+        loop {
+            let (tok, span) = lex.next_spanned_token();
+            let (owned, owned_span) = stream.next_spanned_token().unwrap();
+            assert_eq!(owned.as_token(), tok, "{input:?}");
+            assert_eq!(owned_span, span, "{input:?}");
+
+            if tok == Token::EndOfFile {
+                break;
+            }
+        }
+        assert_eq!(stream.errors(), lex.errors(), "{input:?}");
+    });
+}
+
+#[test]
+fn test_streaming_lexer_iterator() {
+    let input: &[u8] = b"let x = 5;\nx";
+
+    let tokens: Vec<OwnedToken> = StreamingLexer::new(input)
+        .collect::<io::Result<_>>()
+        .unwrap();
+
+    assert_eq!(
+        tokens,
+        vec![
+            OwnedToken::Other(Token::Let),
+            OwnedToken::Identifier(String::from("x")),
+            OwnedToken::Other(Token::Assign),
+            OwnedToken::Other(Token::Integer(5)),
+            OwnedToken::Other(Token::Semicolon),
+            OwnedToken::Identifier(String::from("x")),
+        ]
+    );
+}
+
+//comments and lines longer than a chunk are never kept in the buffer whole
+#[test]
+fn test_streaming_lexer_buffer() {
+    let long: String = "a".repeat(100 * CHUNK_SIZE);
+    let inputs: Vec<(String, usize)> = vec![
+        (format!("x /* {long} */ y"), 2),
+        (format!("x // {long}\ny"), 2),
+        ("x ".repeat(100 * CHUNK_SIZE), 100 * CHUNK_SIZE),
+    ];
+
+    inputs.into_iter().for_each(|(input, tokens)| {
+        let mut stream: StreamingLexer<&[u8]> = StreamingLexer::new(input.as_bytes());
+        while !stream.finished {
+            stream.read_tokens().unwrap();
+            assert!(stream.buffer.len() <= 2 * CHUNK_SIZE);
+        }
+        assert_eq!(stream.tokens.len(), tokens);
+        assert!(stream.errors().is_empty());
+    });
+}
+
 //a test to verbosely check that code is being tokenised correctly by the lexer
 #[test]
 fn visible_test_token() {
@@ -324,7 +1279,7 @@ fn visible_test_token() {
     };
     
     let result = add(five, ten);
-    !-/*5;
+    !-/ *5;
     5 < 10 > 5;
     
     if (5 < 10) {
@@ -345,3 +1300,39 @@ fn visible_test_token() {
         }
     }
 }
+
+#[cfg(test)]
+proptest::proptest! {
+    //no input can make the lexer panic, and every token it returns is a slice of the input on char boundaries
+    #[test]
+    fn test_lexes_any_input(input in "[0-9٣１a-z_é =;(){}/*\n]*|\\PC*") {
+        let mut lex: Lexer = Lexer::new(&input);
+        loop {
+            let (token, span): (Token, Span) = lex.next_spanned_token();
+            proptest::prop_assert!(input.get(span.start..span.end).is_some());
+            if token == Token::EndOfFile {
+                break;
+            }
+        }
+
+        let text: String = LosslessLexer::new(&input).map(|token| token.text).collect();
+        proptest::prop_assert_eq!(text, input);
+    }
+
+    //however comments are spread over lines and the input is split into chunks, the streaming lexer gives the same tokens and errors as lexing the whole input
+    #[test]
+    fn test_streaming_matches_lexer(input in "([a-z0-9 ;=/*é]|/\\*|\\*/|//|\n)*", chunk in 1..8usize) {
+        let mut lex: Lexer = Lexer::new(&input);
+        let mut stream: StreamingLexer<Trickle> = StreamingLexer::new(Trickle(input.as_bytes(), chunk));
+        loop {
+            let (tok, span): (Token, Span) = lex.next_spanned_token();
+            let (owned, owned_span): (OwnedToken, Span) = stream.next_spanned_token().unwrap();
+            proptest::prop_assert_eq!(owned.as_token(), tok);
+            proptest::prop_assert_eq!(owned_span, span);
+            if tok == Token::EndOfFile {
+                break;
+            }
+        }
+        proptest::prop_assert_eq!(stream.errors(), lex.errors());
+    }
+}
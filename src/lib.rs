@@ -1,5 +1,16 @@
 pub mod ast;
+pub mod conformance;
+pub mod environment;
+pub mod evaluator;
+pub mod files;
+pub mod formatter;
+pub mod json;
 pub mod lexer;
+pub mod linter;
+pub mod lsp;
+pub mod object;
 pub mod parser;
 pub mod repl;
+pub mod sexp;
+pub mod test_runner;
 pub mod token;
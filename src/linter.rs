@@ -0,0 +1,654 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::ast::{BlockStatement, Expression, Infix, Program, Spanned, Statement, Visitor};
+use crate::lexer::{Lexer, LosslessKind, LosslessLexer, Trivia};
+use crate::object::Builtin;
+use crate::parser::{Parser, ParserError};
+use crate::token::Span;
+
+//every kind of warning the linter gives
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Lint {
+    //a let binding that is never used - names starting with "_" are never warned about
+    UnusedBinding,
+    //a let binding or parameter with the same name as one that can already be seen, which it hides
+    ShadowedName,
+    //an identifier that isn't bound anywhere it can be seen from
+    UndefinedIdentifier,
+    //a statement after a return statement in the same block, which can never be run
+    UnreachableCode,
+    //a call of something that can never be a function - e.g. "5(1)" or "(a + b)(c)"
+    CallNonFunction,
+    //a comparison of a value with itself - e.g. "x == x", which is always the same
+    SelfComparison,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 6] = [
+        Lint::UnusedBinding,
+        Lint::ShadowedName,
+        Lint::UndefinedIdentifier,
+        Lint::UnreachableCode,
+        Lint::CallNonFunction,
+        Lint::SelfComparison,
+    ];
+
+    //a short code for the lint, e.g. "W001"
+    pub fn code(self) -> &'static str {
+        match self {
+            Lint::UnusedBinding => "W001",
+            Lint::ShadowedName => "W002",
+            Lint::UndefinedIdentifier => "W003",
+            Lint::UnreachableCode => "W004",
+            Lint::CallNonFunction => "W005",
+            Lint::SelfComparison => "W006",
+        }
+    }
+
+    //a readable name for the lint, e.g. "unused-binding" - either this or the code can be used to suppress it
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedBinding => "unused-binding",
+            Lint::ShadowedName => "shadowed-name",
+            Lint::UndefinedIdentifier => "undefined-identifier",
+            Lint::UnreachableCode => "unreachable-code",
+            Lint::CallNonFunction => "call-non-function",
+            Lint::SelfComparison => "self-comparison",
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.code(), self.name())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Warning {
+    pub lint: Lint,
+    pub span: Span,
+    pub message: String,
+}
+
+impl Warning {
+    //renders the warning with the line and column it starts on, e.g. "2:5: warning[W001 unused-binding]: x is never used"
+    pub fn render(&self, source: &str) -> String {
+        let (line, column) = self.span.line_column(source);
+        format!("{line}:{column}: warning[{}]: {}", self.lint, self.message)
+    }
+}
+
+//lints the source, returning every warning found in order of where they start.
+//a warning can be suppressed with a comment after the code on the line it starts on, or on its own on the line before, e.g. "// lint: allow unused-binding" or "/* lint: allow W001, W003 */"
+//source that doesn't parse isn't linted, and the errors are returned instead, with where each was found
+pub fn lint(source: &str) -> Result<Vec<Warning>, Vec<(ParserError<'_>, Span)>> {
+    let mut parser: Parser = Parser::new(Lexer::new(source));
+    let program: Program = parser.parse_program();
+    if !parser.errors().is_empty() {
+        return Err(parser.spanned_errors());
+    }
+
+    let mut linter: Linter = Linter::new();
+    linter.visit_program(&program);
+
+    let allowed: Vec<Allow> = allow_comments(source);
+    let mut warnings: Vec<Warning> = linter
+        .warnings
+        .into_iter()
+        .filter(|warning| {
+            let (line, _) = warning.span.line_column(source);
+            !allowed.iter().any(|allow| allow.covers(line, warning.lint))
+        })
+        .collect();
+    warnings.sort_by_key(|warning| warning.span.start);
+
+    Ok(warnings)
+}
+
+//a "lint: allow" comment, and the lints it suppresses
+struct Allow {
+    //the line it covers - the line it is on if it comes after some code, otherwise the line after it ends
+    line: usize,
+    lints: Vec<Lint>,
+}
+
+impl Allow {
+    fn covers(&self, line: usize, lint: Lint) -> bool {
+        line == self.line && self.lints.contains(&lint)
+    }
+}
+
+const ALLOW_DIRECTIVE: &str = "lint: allow";
+
+//finds every comment that suppresses lints
+fn allow_comments(source: &str) -> Vec<Allow> {
+    LosslessLexer::new(source)
+        .filter(|token| {
+            matches!(
+                token.kind,
+                LosslessKind::Trivia(Trivia::LineComment | Trivia::BlockComment)
+            )
+        })
+        .filter_map(|comment| {
+            let (_, directive) = comment.text.split_once(ALLOW_DIRECTIVE)?;
+            let lints: Vec<Lint> = directive
+                .split(|ch: char| ch.is_whitespace() || ch == ',')
+                .filter_map(|word| {
+                    Lint::ALL
+                        .into_iter()
+                        .find(|lint| word == lint.code() || word == lint.name())
+                })
+                .collect();
+
+            let (start, _) = comment.span.line_column(source);
+            let after_code: bool = source[..comment.span.start]
+                .rsplit('\n')
+                .next()
+                .is_some_and(|before| !before.trim().is_empty());
+            let line: usize = if after_code {
+                start
+            } else {
+                start + comment.text.matches('\n').count() + 1
+            };
+
+            Some(Allow { line, lints })
+        })
+        .collect()
+}
+
+//a name bound by a let statement or a function parameter, and everywhere it is used - e.g. for an editor to go from one to the other
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Definition {
+    pub name: String,
+    //the span of the name where it is bound
+    pub span: Span,
+    //parameters aren't warned about if they aren't used, as the function may need to take them anyway
+    pub is_parameter: bool,
+    //what bound the name, printed as source - e.g. "let x = 5 * y;", or "fn(a, b) { ... }" for a parameter (the bodies of functions are left out)
+    pub source: String,
+    //the span of every identifier that refers to it, in order
+    pub references: Vec<Span>,
+}
+
+//finds every name bound in the source, and everywhere each is used - in order of where they are bound
+//source that doesn't parse isn't looked at, and the errors are returned instead, with where each was found
+pub fn definitions(source: &str) -> Result<Vec<Definition>, Vec<(ParserError<'_>, Span)>> {
+    let mut parser: Parser = Parser::new(Lexer::new(source));
+    let program: Program = parser.parse_program();
+    if !parser.errors().is_empty() {
+        return Err(parser.spanned_errors());
+    }
+
+    let mut linter: Linter = Linter::new();
+    linter.visit_program(&program);
+
+    let mut definitions: Vec<Definition> = linter.definitions;
+    definitions.sort_by_key(|definition| definition.span.start);
+    Ok(definitions)
+}
+
+//the names bound in the program or a function body - blocks of if expressions share the scope they are in, as they do when evaluated
+struct Scope<'ast> {
+    bindings: Vec<Definition>,
+    //every name bound by a let anywhere in the scope, even after the point being linted - a function can use a name bound after it, as long as it isn't called until then
+    later: HashSet<&'ast str>,
+    //names from later that have been used by a function before they were bound, and where
+    used_early: HashMap<&'ast str, Vec<Span>>,
+    is_function: bool,
+}
+
+impl<'ast> Scope<'ast> {
+    fn new(statements: &'ast [Spanned<Statement>], is_function: bool) -> Self {
+        Self {
+            bindings: Vec::new(),
+            later: let_names(statements),
+            used_early: HashMap::new(),
+            is_function,
+        }
+    }
+}
+
+//the names bound by let statements in the statements, including those in the blocks of if expressions, but not those in the bodies of functions
+fn let_names(statements: &[Spanned<Statement>]) -> HashSet<&str> {
+    struct LetNames<'ast>(HashSet<&'ast str>);
+
+    impl<'ast> Visitor<'ast> for LetNames<'ast> {
+        fn visit_statement(&mut self, statement: &'ast Spanned<Statement>) {
+            if let Statement::Let(name, _) = &statement.node {
+                self.0.insert(name);
+            }
+            self.walk_statement(statement);
+        }
+
+        fn visit_expression(&mut self, expression: &'ast Spanned<Expression>) {
+            if !matches!(expression.node, Expression::Function { .. }) {
+                self.walk_expression(expression);
+            }
+        }
+    }
+
+    let mut names: LetNames = LetNames(HashSet::new());
+    statements
+        .iter()
+        .for_each(|statement| names.visit_statement(statement));
+    names.0
+}
+
+//walks the program, keeping track of the names that can be seen at each point
+struct Linter<'ast> {
+    scopes: Vec<Scope<'ast>>,
+    warnings: Vec<Warning>,
+    //the bindings of every scope that has been left
+    definitions: Vec<Definition>,
+}
+
+impl<'ast> Linter<'ast> {
+    fn new() -> Self {
+        Self {
+            scopes: Vec::new(),
+            warnings: Vec::new(),
+            definitions: Vec::new(),
+        }
+    }
+
+    fn warn(&mut self, lint: Lint, span: Span, message: String) {
+        self.warnings.push(Warning {
+            lint,
+            span,
+            message,
+        });
+    }
+
+    //lints each statement, warning about the first that comes after a return
+    fn statements(&mut self, statements: &'ast [Spanned<Statement>]) {
+        let mut returned: bool = false;
+        let mut warned: bool = false;
+
+        for statement in statements {
+            self.visit_statement(statement);
+            if returned && !warned {
+                self.warn(
+                    Lint::UnreachableCode,
+                    statement.span,
+                    String::from("this can never be run, as it comes after a return"),
+                );
+                warned = true;
+            }
+            returned |= matches!(statement.node, Statement::Return(_));
+        }
+    }
+
+    //binds the name in the innermost scope, warning if it hides a name that could already be seen
+    fn bind(&mut self, name: &'ast str, span: Span, is_parameter: bool, source: String) {
+        if self.find(name).is_some() {
+            self.warn(
+                Lint::ShadowedName,
+                span,
+                format!("{name} hides a binding of the same name"),
+            );
+        }
+
+        let scope: &mut Scope<'ast> = self.scopes.last_mut().expect("always in a scope");
+        let references: Vec<Span> = scope.used_early.remove(name).unwrap_or_default();
+        scope.bindings.push(Definition {
+            name: name.to_string(),
+            span,
+            is_parameter,
+            source,
+            references,
+        });
+    }
+
+    //the most recent binding of the name that can be seen, as (scope index, binding index)
+    fn find(&self, name: &str) -> Option<(usize, usize)> {
+        self.scopes.iter().enumerate().rev().find_map(|(i, scope)| {
+            scope
+                .bindings
+                .iter()
+                .rposition(|binding| binding.name == name)
+                .map(|j| (i, j))
+        })
+    }
+
+    //adds the identifier to the references of the binding it refers to, or warns if there isn't one
+    fn resolve(&mut self, name: &'ast str, span: Span) {
+        if let Some((i, j)) = self.find(name) {
+            self.scopes[i].bindings[j].references.push(span);
+            return;
+        }
+
+        //inside a function, a name bound later in a scope outside it will be bound by the time the function can be called
+        for i in (0..self.scopes.len()).rev() {
+            let in_function: bool = self.scopes[i + 1..].iter().any(|scope| scope.is_function);
+            if in_function && self.scopes[i].later.contains(name) {
+                self.scopes[i]
+                    .used_early
+                    .entry(name)
+                    .or_default()
+                    .push(span);
+                return;
+            }
+        }
+
+        if Builtin::lookup(name).is_some() {
+            return;
+        }
+
+        self.warn(
+            Lint::UndefinedIdentifier,
+            span,
+            format!("{name} isn't bound anywhere it can be used from"),
+        );
+    }
+
+    //leaves the innermost scope, warning about any let bindings in it that were never used - test_* functions in the program's scope are used by "monkey test"
+    fn pop_scope(&mut self) {
+        let scope: Scope<'ast> = self.scopes.pop().expect("always in a scope");
+        let is_program: bool = self.scopes.is_empty();
+        for binding in scope.bindings {
+            let may_be_unused: bool = binding.is_parameter
+                || binding.name.starts_with('_')
+                || (is_program && binding.name.starts_with("test_"));
+            if binding.references.is_empty() && !may_be_unused {
+                self.warn(
+                    Lint::UnusedBinding,
+                    binding.span,
+                    format!("{} is never used", binding.name),
+                );
+            }
+            self.definitions.push(binding);
+        }
+    }
+}
+
+//the nodes that bind or use names, or that can be warned about, are dealt with here - anything else is just walked through
+impl<'ast> Visitor<'ast> for Linter<'ast> {
+    fn visit_program(&mut self, program: &'ast Program) {
+        self.scopes.push(Scope::new(&program.statements, false));
+        self.statements(&program.statements);
+        self.pop_scope();
+    }
+
+    //blocks of if expressions share the scope they are in - function bodies get their own in visit_expression()
+    fn visit_block(&mut self, block: &'ast Spanned<BlockStatement>) {
+        self.statements(&block.statements);
+    }
+
+    fn visit_statement(&mut self, statement: &'ast Spanned<Statement>) {
+        let Statement::Let(name, value) = &statement.node else {
+            self.walk_statement(statement);
+            return;
+        };
+
+        //the value is linted first, as the name isn't bound until after it is evaluated
+        self.visit_expression(value);
+        let source: String = match &value.node {
+            Expression::Function { parameters, .. } => {
+                format!("let {} = fn({}) {{ ... }};", name.node, names(parameters))
+            }
+            _ => statement.node.to_string(),
+        };
+        self.bind(name, name.span, false, source);
+    }
+
+    fn visit_expression(&mut self, expression: &'ast Spanned<Expression>) {
+        match &expression.node {
+            Expression::Identifier(name) => self.resolve(name, expression.span),
+            Expression::Infix(operator, left, right) => {
+                self.walk_expression(expression);
+
+                if is_comparison(*operator) && left == right && !has_call(left) {
+                    self.warn(
+                        Lint::SelfComparison,
+                        expression.span,
+                        format!("comparing {left} with itself is always the same"),
+                    );
+                }
+            }
+            Expression::Function { parameters, body } => {
+                self.scopes.push(Scope::new(&body.statements, true));
+                let source: String = format!("fn({}) {{ ... }}", names(parameters));
+                parameters.iter().for_each(|parameter| {
+                    self.bind(parameter, parameter.span, true, source.clone());
+                });
+                self.visit_block(body);
+                self.pop_scope();
+            }
+            Expression::Call { function, .. } => {
+                self.walk_expression(expression);
+
+                if matches!(
+                    function.node,
+                    Expression::Integer(_)
+                        | Expression::Boolean(_)
+                        | Expression::Prefix(..)
+                        | Expression::Infix(..)
+                ) {
+                    self.warn(
+                        Lint::CallNonFunction,
+                        function.span,
+                        format!("{function} can never be a function, so can't be called"),
+                    );
+                }
+            }
+            //the value is linted first, as it is evaluated before the name is assigned to
+            Expression::Assign(name, value) => {
+                self.visit_expression(value);
+                self.resolve(name, name.span);
+            }
+            _ => self.walk_expression(expression),
+        }
+    }
+}
+
+//the names of parameters, as they are written in a function - e.g. "a, b"
+fn names(parameters: &[Spanned<String>]) -> String {
+    parameters
+        .iter()
+        .map(|parameter| parameter.as_str())
+        .collect::<Vec<&str>>()
+        .join(", ")
+}
+
+fn is_comparison(operator: Infix) -> bool {
+    matches!(
+        operator,
+        Infix::Equal
+            | Infix::NotEqual
+            | Infix::LessThan
+            | Infix::GreaterThan
+            | Infix::LessThanEqual
+            | Infix::GreaterThanEqual
+    )
+}
+
+//whether evaluating the expression could call a function or assign to a name - if so, it could give a different value each time.
+//an if expression counts too, as its blocks can hold any statement - but the body of a function doesn't, as making the function doesn't run it
+fn has_call(expression: &Spanned<Expression>) -> bool {
+    struct HasCall(bool);
+
+    impl<'ast> Visitor<'ast> for HasCall {
+        fn visit_expression(&mut self, expression: &'ast Spanned<Expression>) {
+            match &expression.node {
+                Expression::Call { .. } | Expression::If { .. } => self.0 = true,
+                Expression::Function { .. } => {}
+                _ => self.walk_expression(expression),
+            }
+        }
+
+        //blocks are never walked into, so the only names reached are the ones being assigned to
+        fn visit_name(&mut self, _name: &'ast Spanned<String>) {
+            self.0 = true;
+        }
+    }
+
+    let mut visitor: HasCall = HasCall(false);
+    visitor.visit_expression(expression);
+    visitor.0
+}
+
+#[cfg(test)]
+fn lint_codes(source: &str) -> Vec<(&'static str, &str)> {
+    lint(source)
+        .unwrap()
+        .into_iter()
+        .map(|warning| {
+            (
+                warning.lint.code(),
+                &source[warning.span.start..warning.span.end],
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_lint() {
+    let tests: Vec<(&str, Vec<(&str, &str)>)> = vec![
+        ("let x = 5; x", vec![]),
+        ("let x = 5;", vec![("W001", "x")]),
+        ("let _x = 5;", vec![]),
+        //parameters don't have to be used
+        ("let f = fn(a, b) { a }; f(1, 2)", vec![]),
+        ("let x = 1; let f = fn(x) { x }; f(x)", vec![("W002", "x")]),
+        ("let x = 1; let x = x + 1; x", vec![("W002", "x")]),
+        ("y + 1", vec![("W003", "y")]),
+        ("let x = x + 1; x", vec![("W003", "x")]),
+        ("assert_eq(1, 1)", vec![]),
+        ("let x = 1; x = 2;", vec![]),
+        ("y = 2;", vec![("W003", "y")]),
+        ("let f = fn() { y = 2 }; let y = 1; f()", vec![]),
+        ("let x = 1; (x = x + 1) == (x = x + 1)", vec![]),
+        (
+            "let test_one = fn() { let test_two = 2; };",
+            vec![("W001", "test_two")],
+        ),
+        //a function can use a name bound after it, or itself
+        ("let a = fn() { b() }; let b = fn() { a() }; a()", vec![]),
+        (
+            "let fact = fn(n) { if (n < 2) { 1 } else { n * fact(n - 1) } }; fact(5)",
+            vec![],
+        ),
+        //but not outside a function
+        ("b; let b = 1; b", vec![("W003", "b")]),
+        //if blocks share the scope they are in
+        ("if (true) { let y = 1; }; y", vec![]),
+        (
+            "let f = fn() { return 1; let y = 2; y; }; f()",
+            vec![("W004", "let y = 2")],
+        ),
+        ("return 1; 2; 3", vec![("W004", "2")]),
+        (
+            "5(1); true(); (1 + 2)(3); fn(x) { x }(1)",
+            vec![("W005", "5"), ("W005", "true"), ("W005", "1 + 2")],
+        ),
+        ("let x = 1; x == x; x < 2", vec![("W006", "x == x")]),
+        ("let f = fn() { 1 }; f() == f()", vec![]),
+        ("let a = 1; -a != -a", vec![("W006", "-a != -a")]),
+    ];
+
+    tests.into_iter().for_each(|(source, expected)| {
+        assert_eq!(lint_codes(source), expected, "{source}");
+    });
+}
+
+#[test]
+fn test_definitions() {
+    let source: &str = "let add = fn(a, b) { a + b };
+let x = add(1, 2) * 3;
+let call = fn() { later(x) };
+let later = fn(y) { y };
+add(x, call())";
+
+    let definitions: Vec<String> = definitions(source)
+        .unwrap()
+        .into_iter()
+        .map(|definition| {
+            let (line, column) = definition.span.line_column(source);
+            let references: Vec<String> = definition
+                .references
+                .iter()
+                .map(|span| {
+                    let (line, column) = span.line_column(source);
+                    format!("{line}:{column}")
+                })
+                .collect();
+
+            format!(
+                "{} at {line}:{column}{} - {} - used at [{}]",
+                definition.name,
+                if definition.is_parameter {
+                    " (parameter)"
+                } else {
+                    ""
+                },
+                definition.source,
+                references.join(", ")
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        definitions,
+        vec![
+            "add at 1:5 - let add = fn(a, b) { ... }; - used at [2:9, 5:1]",
+            "a at 1:14 (parameter) - fn(a, b) { ... } - used at [1:22]",
+            "b at 1:17 (parameter) - fn(a, b) { ... } - used at [1:26]",
+            "x at 2:5 - let x = add(1, 2) * 3; - used at [3:25, 5:5]",
+            "call at 3:5 - let call = fn() { ... }; - used at [5:8]",
+            //used by call before it was bound
+            "later at 4:5 - let later = fn(y) { ... }; - used at [3:19]",
+            "y at 4:16 (parameter) - fn(y) { ... } - used at [4:21]",
+        ]
+    );
+}
+
+#[test]
+fn test_lint_suppression() {
+    let source: &str = "// lint: allow unused-binding
+let a = 1;
+let b = 1; // lint: allow W001
+let c = 1; /* lint: allow shadowed-name */
+/* lint: allow
+   W003 undefined-identifier */
+d;
+d;";
+
+    assert_eq!(lint_codes(source), vec![("W001", "c"), ("W003", "d")]);
+}
+
+#[test]
+fn test_lint_render() {
+    let source: &str = "let x = 5;\nlet y = x;";
+    let warnings: Vec<Warning> = lint(source).unwrap();
+
+    assert_eq!(
+        warnings
+            .iter()
+            .map(|warning| warning.render(source))
+            .collect::<Vec<String>>(),
+        vec!["2:5: warning[W001 unused-binding]: y is never used"]
+    );
+    assert!(lint("let = 5;").is_err());
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    //however the source is written, every warning covers the source of what it is about
+    #[test]
+    fn test_lint_spans(program in crate::ast::strategies::program()) {
+        for source in [program.to_string(), format!("{program:#}")] {
+            for warning in lint(&source).unwrap() {
+                let text: &str = &source[warning.span.start..warning.span.end];
+                let expected: bool = match warning.lint {
+                    Lint::UnusedBinding | Lint::ShadowedName | Lint::UndefinedIdentifier => {
+                        warning.message.starts_with(text)
+                    }
+                    _ => !text.is_empty(),
+                };
+                proptest::prop_assert!(expected, "{:?} in {}", warning, source);
+            }
+        }
+    }
+}
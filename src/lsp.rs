@@ -0,0 +1,755 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+    Notification as NotificationTrait, PublishDiagnostics,
+};
+use lsp_types::request::{
+    Formatting, GotoDefinition, HoverRequest, References, Request as RequestTrait,
+    SemanticTokensFullRequest,
+};
+use lsp_types::{
+    Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentFormattingParams, GotoDefinitionParams,
+    GotoDefinitionResponse, Hover, HoverContents, HoverParams, HoverProviderCapability, Location,
+    MarkupContent, MarkupKind, NumberOrString, OneOf, Position, PublishDiagnosticsParams, Range,
+    ReferenceParams, SemanticToken, SemanticTokenType, SemanticTokens, SemanticTokensFullOptions,
+    SemanticTokensLegend, SemanticTokensOptions, SemanticTokensParams, SemanticTokensResult,
+    SemanticTokensServerCapabilities, ServerCapabilities, TextDocumentPositionParams,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Uri,
+};
+use serde_json::Value;
+
+use crate::formatter::format;
+use crate::lexer::{Lexer, LosslessKind, LosslessLexer, Trivia};
+use crate::linter::{definitions, lint, Definition, Warning};
+use crate::parser::Parser;
+use crate::token::{Span, Token};
+
+//the kinds of semantic token the server gives - a token's type is its index in this list
+const TOKEN_TYPES: [SemanticTokenType; 5] = [
+    SemanticTokenType::KEYWORD,
+    SemanticTokenType::VARIABLE,
+    SemanticTokenType::NUMBER,
+    SemanticTokenType::OPERATOR,
+    SemanticTokenType::COMMENT,
+];
+const KEYWORD: u32 = 0;
+const VARIABLE: u32 = 1;
+const NUMBER: u32 = 2;
+const OPERATOR: u32 = 3;
+const COMMENT: u32 = 4;
+
+pub type LspError = Box<dyn Error + Sync + Send>;
+
+//runs a language server for monkey over stdin and stdout, until the client shuts it down
+pub fn serve_stdio() -> Result<(), LspError> {
+    let (connection, io_threads) = Connection::stdio();
+    serve(&connection)?;
+    //the thread writing to stdout only stops once the connection's sender is gone
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+//runs the language server over any connection (e.g. an in-memory one in tests), until the client shuts it down
+pub fn serve(connection: &Connection) -> Result<(), LspError> {
+    connection.initialize(serde_json::to_value(capabilities())?)?;
+
+    let mut server: Server = Server::default();
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                connection
+                    .sender
+                    .send(Message::Response(server.request(request)))?;
+            }
+            Message::Notification(notification) => match server.notification(notification) {
+                Ok(Some(diagnostics)) => connection.sender.send(diagnostics.into())?,
+                Ok(None) => {}
+                //notifications can't be answered, so there's no way to tell the client its message was wrong
+                Err(err) => eprintln!("couldn't handle a notification: {err}"),
+            },
+            Message::Response(_) => {}
+        }
+    }
+
+    Ok(())
+}
+
+//what the server can do, sent to the client when it starts
+fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        semantic_tokens_provider: Some(SemanticTokensServerCapabilities::SemanticTokensOptions(
+            SemanticTokensOptions {
+                legend: SemanticTokensLegend {
+                    token_types: TOKEN_TYPES.to_vec(),
+                    token_modifiers: Vec::new(),
+                },
+                full: Some(SemanticTokensFullOptions::Bool(true)),
+                ..SemanticTokensOptions::default()
+            },
+        )),
+        definition_provider: Some(OneOf::Left(true)),
+        references_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        ..ServerCapabilities::default()
+    }
+}
+
+//reads the params of a request, and turns what the handler returns into the result
+fn handle<R: RequestTrait>(
+    params: Value,
+    handler: impl FnOnce(R::Params) -> R::Result,
+) -> Result<Value, serde_json::Error> {
+    let params: R::Params = serde_json::from_value(params)?;
+    serde_json::to_value(handler(params))
+}
+
+//the text of every document the client has open - everything else is worked out from it again for each request, as monkey files are small
+#[derive(Default)]
+struct Server {
+    documents: HashMap<Uri, String>,
+}
+
+impl Server {
+    //answers a request from the client - requests about documents that aren't open, or that can't be answered as the document doesn't parse, get a null result
+    fn request(&self, request: Request) -> Response {
+        let result: Result<Value, serde_json::Error> = match request.method.as_str() {
+            SemanticTokensFullRequest::METHOD => {
+                handle::<SemanticTokensFullRequest>(request.params, |params| {
+                    self.semantic_tokens(params)
+                })
+            }
+            GotoDefinition::METHOD => {
+                handle::<GotoDefinition>(request.params, |params| self.definition(params))
+            }
+            References::METHOD => {
+                handle::<References>(request.params, |params| self.references(params))
+            }
+            HoverRequest::METHOD => {
+                handle::<HoverRequest>(request.params, |params| self.hover(params))
+            }
+            Formatting::METHOD => {
+                handle::<Formatting>(request.params, |params| self.formatting(params))
+            }
+            method => {
+                return Response::new_err(
+                    request.id,
+                    ErrorCode::MethodNotFound as i32,
+                    format!("unknown request {method}"),
+                )
+            }
+        };
+
+        match result {
+            Ok(result) => Response::new_ok(request.id, result),
+            Err(err) => {
+                Response::new_err(request.id, ErrorCode::InvalidParams as i32, err.to_string())
+            }
+        }
+    }
+
+    //keeps track of the documents the client has open - when one changes, the diagnostics for it are returned to send back
+    fn notification(
+        &mut self,
+        notification: Notification,
+    ) -> Result<Option<Notification>, serde_json::Error> {
+        let (uri, diagnostics): (Uri, Vec<Diagnostic>) = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: DidOpenTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                let diagnostics: Vec<Diagnostic> = diagnostics(&params.text_document.text);
+                self.documents
+                    .insert(params.text_document.uri.clone(), params.text_document.text);
+                (params.text_document.uri, diagnostics)
+            }
+            DidChangeTextDocument::METHOD => {
+                let mut params: DidChangeTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                //the server asks for the whole document on every change, so only the last one matters
+                let Some(change) = params.content_changes.pop() else {
+                    return Ok(None);
+                };
+                let diagnostics: Vec<Diagnostic> = diagnostics(&change.text);
+                self.documents
+                    .insert(params.text_document.uri.clone(), change.text);
+                (params.text_document.uri, diagnostics)
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: DidCloseTextDocumentParams =
+                    serde_json::from_value(notification.params)?;
+                self.documents.remove(&params.text_document.uri);
+                //clears the diagnostics of the closed document
+                (params.text_document.uri, Vec::new())
+            }
+            _ => return Ok(None),
+        };
+
+        let params: PublishDiagnosticsParams = PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        };
+        Ok(Some(Notification::new(
+            PublishDiagnostics::METHOD.to_string(),
+            params,
+        )))
+    }
+
+    fn semantic_tokens(&self, params: SemanticTokensParams) -> Option<SemanticTokensResult> {
+        let text: &String = self.documents.get(&params.text_document.uri)?;
+        Some(SemanticTokensResult::Tokens(SemanticTokens {
+            result_id: None,
+            data: semantic_tokens(text),
+        }))
+    }
+
+    fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let position: &TextDocumentPositionParams = &params.text_document_position_params;
+        self.find_definition(position, |lines, definition, _| {
+            GotoDefinitionResponse::Scalar(Location {
+                uri: position.text_document.uri.clone(),
+                range: lines.range(definition.span),
+            })
+        })
+    }
+
+    fn references(&self, params: ReferenceParams) -> Option<Vec<Location>> {
+        let position: &TextDocumentPositionParams = &params.text_document_position;
+        self.find_definition(position, |lines, definition, _| {
+            let declaration: Option<Span> = if params.context.include_declaration {
+                Some(definition.span)
+            } else {
+                None
+            };
+
+            declaration
+                .into_iter()
+                .chain(definition.references.iter().copied())
+                .map(|span| Location {
+                    uri: position.text_document.uri.clone(),
+                    range: lines.range(span),
+                })
+                .collect()
+        })
+    }
+
+    //shows what bound the name under the cursor, as source
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        self.find_definition(
+            &params.text_document_position_params,
+            |lines, definition, hovered| Hover {
+                contents: HoverContents::Markup(MarkupContent {
+                    kind: MarkupKind::Markdown,
+                    value: format!("```monkey\n{}\n```", definition.source),
+                }),
+                range: Some(lines.range(hovered)),
+            },
+        )
+    }
+
+    //formats the whole document as one edit - there are no edits if it is already formatted
+    fn formatting(&self, params: DocumentFormattingParams) -> Option<Vec<TextEdit>> {
+        let text: &String = self.documents.get(&params.text_document.uri)?;
+        let formatted: String = format(text).ok()?;
+        if formatted == *text {
+            return Some(Vec::new());
+        }
+
+        let lines: Lines = Lines::new(text);
+        Some(vec![TextEdit {
+            range: lines.range(Span::new(0, text.len())),
+            new_text: formatted,
+        }])
+    }
+
+    //finds the definition of the name at a position, whether it is where the name is bound or where it is used, and passes it to found along with the span of the name at the position
+    fn find_definition<T>(
+        &self,
+        position: &TextDocumentPositionParams,
+        found: impl FnOnce(&Lines, &Definition, Span) -> T,
+    ) -> Option<T> {
+        let text: &String = self.documents.get(&position.text_document.uri)?;
+        let lines: Lines = Lines::new(text);
+        let offset: usize = lines.offset(position.position)?;
+        //a cursor just after a name is still on it
+        let contains = |span: &Span| span.start <= offset && offset <= span.end;
+
+        let definitions: Vec<Definition> = definitions(text).ok()?;
+        let (definition, hovered): (&Definition, Span) =
+            definitions.iter().find_map(|definition| {
+                let hovered: &Span = std::iter::once(&definition.span)
+                    .chain(&definition.references)
+                    .find(|span| contains(span))?;
+                Some((definition, *hovered))
+            })?;
+        Some(found(&lines, definition, hovered))
+    }
+}
+
+//the errors in the source, or if it parses, the lint warnings
+fn diagnostics(text: &str) -> Vec<Diagnostic> {
+    let lines: Lines = Lines::new(text);
+
+    let mut parser: Parser = Parser::new(Lexer::new(text));
+    parser.parse_program();
+    if !parser.errors().is_empty() {
+        return parser
+            .errors()
+            .iter()
+            .zip(parser.error_spans())
+            .map(|(error, span)| Diagnostic {
+                range: lines.range(*span),
+                severity: Some(DiagnosticSeverity::ERROR),
+                source: Some(String::from("monkey")),
+                message: error.to_string(),
+                ..Diagnostic::default()
+            })
+            .collect();
+    }
+
+    let warnings: Vec<Warning> = lint(text).unwrap_or_default();
+    warnings
+        .into_iter()
+        .map(|warning| Diagnostic {
+            range: lines.range(warning.span),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: Some(NumberOrString::String(warning.lint.to_string())),
+            source: Some(String::from("monkey")),
+            message: warning.message,
+            ..Diagnostic::default()
+        })
+        .collect()
+}
+
+//the semantic tokens of the source, taken straight from the lexer - each is given relative to the one before it, as the protocol expects
+fn semantic_tokens(text: &str) -> Vec<SemanticToken> {
+    let lines: Lines = Lines::new(text);
+    let mut tokens: Vec<SemanticToken> = Vec::new();
+    let mut previous: Position = Position::default();
+
+    for token in LosslessLexer::new(text) {
+        let token_type: u32 = match token.kind {
+            LosslessKind::Trivia(Trivia::LineComment | Trivia::BlockComment) => COMMENT,
+            LosslessKind::Trivia(_) => continue,
+            LosslessKind::Token(token) => match token {
+                Token::Identifier(_) => VARIABLE,
+                Token::Integer(_) => NUMBER,
+                Token::Function
+                | Token::Let
+                | Token::True
+                | Token::False
+                | Token::If
+                | Token::Else
+                | Token::Return => KEYWORD,
+                Token::Comma
+                | Token::Semicolon
+                | Token::LeftParenthesis
+                | Token::RightParenthesis
+                | Token::LeftBrace
+                | Token::RightBrace
+                | Token::Illegal
+                | Token::EndOfFile => continue,
+                _ => OPERATOR,
+            },
+        };
+
+        //not every client can show a token over more than one line, so block comments are split into one token per line
+        let mut start: usize = token.span.start;
+        for line in token.text.split_inclusive('\n') {
+            let content: &str = line.trim_end_matches(['\r', '\n']);
+            if !content.is_empty() {
+                let position: Position = lines.position(start);
+                let delta_line: u32 = position.line - previous.line;
+                tokens.push(SemanticToken {
+                    delta_line,
+                    delta_start: if delta_line == 0 {
+                        position.character - previous.character
+                    } else {
+                        position.character
+                    },
+                    length: content.encode_utf16().count() as u32,
+                    token_type,
+                    token_modifiers_bitset: 0,
+                });
+                previous = position;
+            }
+            start += line.len();
+        }
+    }
+
+    tokens
+}
+
+//converts between byte offsets into a document and the positions of the protocol, which count lines from 0 and columns in UTF-16 code units
+struct Lines<'text> {
+    text: &'text str,
+    //the offset every line starts at
+    starts: Vec<usize>,
+}
+
+impl<'text> Lines<'text> {
+    fn new(text: &'text str) -> Self {
+        let starts: Vec<usize> = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(newline, _)| newline + 1))
+            .collect();
+        Self { text, starts }
+    }
+
+    fn position(&self, offset: usize) -> Position {
+        let offset: usize = offset.min(self.text.len());
+        let line: usize = self.starts.partition_point(|&start| start <= offset) - 1;
+        let character: usize = self.text[self.starts[line]..offset].encode_utf16().count();
+        Position::new(line as u32, character as u32)
+    }
+
+    //a position past the end of its line is taken to be the end of it - there is no offset for a line that doesn't exist
+    fn offset(&self, position: Position) -> Option<usize> {
+        let start: usize = *self.starts.get(position.line as usize)?;
+        let end: usize = self
+            .starts
+            .get(position.line as usize + 1)
+            .copied()
+            .unwrap_or(self.text.len());
+        let line: &str = self.text[start..end].trim_end_matches(['\r', '\n']);
+
+        let mut character: u32 = 0;
+        for (index, ch) in line.char_indices() {
+            if character >= position.character {
+                return Some(start + index);
+            }
+            character += ch.len_utf16() as u32;
+        }
+        Some(start + line.len())
+    }
+
+    fn range(&self, span: Span) -> Range {
+        Range::new(self.position(span.start), self.position(span.end))
+    }
+}
+
+#[cfg(test)]
+struct TestClient {
+    connection: Connection,
+    next_id: i32,
+    //the server, running on its own thread until the client stops it
+    server: std::thread::JoinHandle<Result<(), String>>,
+}
+
+#[cfg(test)]
+impl TestClient {
+    //starts a server on another thread, connected to a new client that has already initialised it
+    fn start() -> Self {
+        use lsp_types::notification::Initialized;
+        use lsp_types::request::Initialize;
+        use lsp_types::{InitializeParams, InitializeResult, InitializedParams};
+
+        let (server, client) = Connection::memory();
+        let mut client: TestClient = TestClient {
+            connection: client,
+            next_id: 0,
+            server: std::thread::spawn(move || serve(&server).map_err(|err| err.to_string())),
+        };
+
+        let result: InitializeResult = client.request::<Initialize>(InitializeParams::default());
+        assert_eq!(result.capabilities, capabilities());
+        client.notify::<Initialized>(InitializedParams {});
+        client
+    }
+
+    //shuts the server down, failing the test if it stopped with an error
+    fn stop(mut self) {
+        use lsp_types::notification::Exit;
+        use lsp_types::request::Shutdown;
+
+        self.request::<Shutdown>(());
+        self.notify::<Exit>(());
+        self.server.join().unwrap().unwrap();
+    }
+
+    //sends a request to the server and waits for the result - the server must not send anything else first
+    fn request<R: RequestTrait>(&mut self, params: R::Params) -> R::Result {
+        self.next_id += 1;
+        let request: Request = Request::new(self.next_id.into(), R::METHOD.to_string(), params);
+        self.connection.sender.send(request.into()).unwrap();
+
+        match self.connection.receiver.recv().unwrap() {
+            Message::Response(response) => {
+                assert_eq!(response.id, self.next_id.into());
+                assert!(response.error.is_none(), "{:?}", response.error);
+                serde_json::from_value(response.result.unwrap_or(Value::Null)).unwrap()
+            }
+            message => panic!("expected a response to {}, got {message:?}", R::METHOD),
+        }
+    }
+
+    fn notify<N: NotificationTrait>(&mut self, params: N::Params) {
+        let notification: Notification = Notification::new(N::METHOD.to_string(), params);
+        self.connection.sender.send(notification.into()).unwrap();
+    }
+
+    //waits for the diagnostics the server publishes after a document is opened, changed or closed
+    fn diagnostics(&mut self) -> Vec<Diagnostic> {
+        match self.connection.receiver.recv().unwrap() {
+            Message::Notification(notification) => {
+                let params: PublishDiagnosticsParams =
+                    notification.extract(PublishDiagnostics::METHOD).unwrap();
+                params.diagnostics
+            }
+            message => panic!("expected diagnostics, got {message:?}"),
+        }
+    }
+}
+
+#[test]
+fn test_lines() {
+    let text: &str = "let é = 1;\n😀x\r\n";
+    let lines: Lines = Lines::new(text);
+
+    assert_eq!(lines.position(0), Position::new(0, 0));
+    assert_eq!(lines.position(7), Position::new(0, 6));
+    assert_eq!(lines.position(16), Position::new(1, 2));
+    assert_eq!(lines.position(text.len()), Position::new(2, 0));
+
+    assert_eq!(lines.offset(Position::new(0, 6)), Some(7));
+    assert_eq!(lines.offset(Position::new(1, 2)), Some(16));
+    assert_eq!(lines.offset(Position::new(1, 40)), Some(17));
+    assert_eq!(lines.offset(Position::new(3, 0)), None);
+}
+
+#[test]
+fn test_semantic_tokens() {
+    let token = |delta_line: u32, delta_start: u32, length: u32, token_type: u32| SemanticToken {
+        delta_line,
+        delta_start,
+        length,
+        token_type,
+        token_modifiers_bitset: 0,
+    };
+
+    assert_eq!(
+        semantic_tokens("let x = -5; /* a\n comment */\nif (x) { true }"),
+        vec![
+            token(0, 0, 3, KEYWORD),
+            token(0, 4, 1, VARIABLE),
+            token(0, 2, 1, OPERATOR),
+            token(0, 2, 1, OPERATOR),
+            token(0, 1, 1, NUMBER),
+            token(0, 3, 4, COMMENT),
+            token(1, 0, 11, COMMENT),
+            token(1, 0, 2, KEYWORD),
+            token(0, 4, 1, VARIABLE),
+            token(0, 5, 4, KEYWORD),
+        ]
+    );
+}
+
+#[test]
+fn test_scripted_client() {
+    use lsp_types::{
+        DocumentFormattingParams, ReferenceContext, TextDocumentContentChangeEvent,
+        TextDocumentIdentifier, TextDocumentItem, VersionedTextDocumentIdentifier,
+    };
+
+    let mut client: TestClient = TestClient::start();
+    let uri: Uri = "file:///test.monkey".parse().unwrap();
+    let document = || TextDocumentIdentifier { uri: uri.clone() };
+    let at = |line: u32, character: u32| TextDocumentPositionParams {
+        text_document: document(),
+        position: Position::new(line, character),
+    };
+    let location = |line: u32, start: u32, end: u32| Location {
+        uri: uri.clone(),
+        range: Range::new(Position::new(line, start), Position::new(line, end)),
+    };
+
+    client.notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+        text_document: TextDocumentItem {
+            uri: uri.clone(),
+            language_id: String::from("monkey"),
+            version: 1,
+            text: String::from("let add = fn(a, b) { a + b };\nlet x = add(1, 2);\nx"),
+        },
+    });
+    assert_eq!(client.diagnostics(), Vec::new());
+
+    //from a use to where it is bound
+    assert_eq!(
+        client.request::<GotoDefinition>(GotoDefinitionParams {
+            text_document_position_params: at(1, 9),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }),
+        Some(GotoDefinitionResponse::Scalar(location(0, 4, 7)))
+    );
+    //nothing is bound at a keyword
+    assert_eq!(
+        client.request::<GotoDefinition>(GotoDefinitionParams {
+            text_document_position_params: at(0, 1),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        }),
+        None
+    );
+
+    assert_eq!(
+        client.request::<References>(ReferenceParams {
+            text_document_position: at(0, 13),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: ReferenceContext {
+                include_declaration: true,
+            },
+        }),
+        Some(vec![location(0, 13, 14), location(0, 21, 22)])
+    );
+    assert_eq!(
+        client.request::<References>(ReferenceParams {
+            text_document_position: at(0, 5),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+            context: ReferenceContext {
+                include_declaration: false,
+            },
+        }),
+        Some(vec![location(1, 8, 11)])
+    );
+
+    let hover: Option<Hover> = client.request::<HoverRequest>(HoverParams {
+        text_document_position_params: at(2, 0),
+        work_done_progress_params: Default::default(),
+    });
+    assert_eq!(
+        hover,
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: String::from("```monkey\nlet x = add(1, 2);\n```"),
+            }),
+            range: Some(location(2, 0, 1).range),
+        })
+    );
+
+    let tokens: Option<SemanticTokensResult> =
+        client.request::<SemanticTokensFullRequest>(SemanticTokensParams {
+            text_document: document(),
+            work_done_progress_params: Default::default(),
+            partial_result_params: Default::default(),
+        });
+    match tokens {
+        Some(SemanticTokensResult::Tokens(tokens)) => assert_eq!(tokens.data.len(), 16),
+        tokens => panic!("expected semantic tokens, got {tokens:?}"),
+    }
+
+    let mut change = |text: &str| {
+        client.notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier {
+                uri: uri.clone(),
+                version: 2,
+            },
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: text.to_string(),
+            }],
+        });
+        client.diagnostics()
+    };
+
+    let diagnostics: Vec<Diagnostic> = change("let x = 5 +;");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+    assert_eq!(diagnostics[0].range, location(0, 11, 12).range);
+
+    let diagnostics: Vec<Diagnostic> = change("let  y = 5;");
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+    assert_eq!(
+        diagnostics[0].code,
+        Some(NumberOrString::String(String::from("W001 unused-binding")))
+    );
+    assert_eq!(diagnostics[0].range, location(0, 5, 6).range);
+
+    let formatting = |client: &mut TestClient| {
+        client.request::<Formatting>(DocumentFormattingParams {
+            text_document: document(),
+            options: Default::default(),
+            work_done_progress_params: Default::default(),
+        })
+    };
+    assert_eq!(
+        formatting(&mut client),
+        Some(vec![TextEdit {
+            range: location(0, 0, 11).range,
+            new_text: String::from("let y = 5;\n"),
+        }])
+    );
+
+    client.notify::<DidCloseTextDocument>(DidCloseTextDocumentParams {
+        text_document: document(),
+    });
+    assert_eq!(client.diagnostics(), Vec::new());
+    assert_eq!(formatting(&mut client), None);
+
+    client.stop();
+}
+
+#[test]
+fn test_scripted_client_bad_literals() {
+    use lsp_types::{
+        TextDocumentContentChangeEvent, TextDocumentItem, VersionedTextDocumentIdentifier,
+    };
+
+    let mut client: TestClient = TestClient::start();
+    let uri: Uri = "file:///test.monkey".parse().unwrap();
+
+    //a literal too large for an i32 is an error in the document, rather than something that stops the server
+    client.notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+        text_document: TextDocumentItem {
+            uri: uri.clone(),
+            language_id: String::from("monkey"),
+            version: 1,
+            text: String::from("let x = 2147483648;"),
+        },
+    });
+    let diagnostics: Vec<Diagnostic> = client.diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+    assert_eq!(
+        diagnostics[0].message,
+        "integer literal 2147483648 is too large"
+    );
+    assert_eq!(
+        diagnostics[0].range,
+        Range::new(Position::new(0, 8), Position::new(0, 18))
+    );
+
+    client.notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+        text_document: VersionedTextDocumentIdentifier {
+            uri: uri.clone(),
+            version: 2,
+        },
+        content_changes: vec![TextDocumentContentChangeEvent {
+            range: None,
+            range_length: None,
+            text: String::from("let x = ٣;"),
+        }],
+    });
+    let diagnostics: Vec<Diagnostic> = client.diagnostics();
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+    assert_eq!(
+        diagnostics[0].message,
+        "expected an expression, got an illegal character"
+    );
+
+    client.stop();
+}
@@ -1,18 +1,326 @@
-use monkey_interpreter::repl::repl;
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use monkey_interpreter::ast::Program;
+use monkey_interpreter::files::monkey_files;
+use monkey_interpreter::formatter::format;
+use monkey_interpreter::json::{ast_json, tokens_json};
+use monkey_interpreter::lexer::Lexer;
+use monkey_interpreter::linter::{lint, Warning};
+use monkey_interpreter::lsp;
+use monkey_interpreter::parser::{self, ParserError};
+use monkey_interpreter::repl::{self, repl, run_script, Mode, ReplOptions};
+use monkey_interpreter::test_runner::{run_tests, TestResult};
+use monkey_interpreter::token::Span;
+use serde_json::Value;
 use users::get_current_username;
 
-//runs a repl of monkey
+//the command line arguments for the monkey binary
+#[derive(Parser)]
+#[command(version, about = "The monkey programming language")]
+struct Cli {
+    #[arg(
+        long,
+        env = "MONKEY_HISTORY",
+        value_name = "FILE",
+        help = "Where to keep the REPL's history [default: monkey/history in the user's data directory]"
+    )]
+    history: Option<PathBuf>,
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = Mode::Eval,
+        help = "What the REPL does with each input - can be switched later with :mode"
+    )]
+    mode: Mode,
+    #[arg(short, long, help = "Don't print the greeting when the REPL starts")]
+    quiet: bool,
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        help = "Print the tokens or AST of the source on stdin as JSON, rather than running it"
+    )]
+    emit: Option<Emit>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+//what --emit prints - the format of each is described in the json module
+#[derive(Clone, Copy, ValueEnum)]
+enum Emit {
+    TokensJson,
+    AstJson,
+}
+
+//tools for working with monkey files - without one, the repl is started
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "Format monkey files in the standard style")]
+    Fmt {
+        #[arg(
+            long,
+            help = "Don't change any files, but exit with an error if any aren't formatted"
+        )]
+        check: bool,
+        #[arg(
+            help = "The files to format, or directories to format every .monkey file in [default: format stdin to stdout]"
+        )]
+        paths: Vec<PathBuf>,
+    },
+    #[command(about = "Warn about likely mistakes in monkey files")]
+    Lint {
+        #[arg(
+            help = "The files to lint, or directories to lint every .monkey file in [default: lint stdin]"
+        )]
+        paths: Vec<PathBuf>,
+    },
+    #[command(about = "Run a language server for editors over stdin and stdout")]
+    Lsp,
+    #[command(about = "Run the test_* functions in monkey files")]
+    Test {
+        #[arg(
+            help = "The files to test, or directories to test every .monkey file in [default: the current directory]"
+        )]
+        paths: Vec<PathBuf>,
+    },
+}
+
+//runs a repl of monkey - or if input is piped in rather than typed, runs all of it as a script
 fn main() {
-    //check the username of the user who ran the command
-    match get_current_username() {
-        Some(name) => println!(
-            "Hello {:?}! This is the monkey programing language REPL.",
-            name
-        ),
-        None => panic!("user doesn't exist"),
+    let cli: Cli = Cli::parse();
+
+    if let Some(command) = cli.command {
+        let succeeded: bool = match command {
+            Command::Fmt { check, paths } => fmt(&paths, check),
+            Command::Lint { paths } => lint_files(&paths),
+            Command::Test { paths } => test_files(&paths),
+            Command::Lsp => match lsp::serve_stdio() {
+                Ok(()) => true,
+                Err(err) => {
+                    eprintln!("the language server stopped: {err}");
+                    false
+                }
+            },
+        };
+        process::exit(if succeeded { 0 } else { 1 });
+    }
+
+    if let Some(emit) = cli.emit {
+        process::exit(if emit_json(emit) { 0 } else { 1 });
+    }
+
+    if !io::stdin().is_terminal() {
+        let mut source: String = String::new();
+        if let Err(err) = io::stdin().read_to_string(&mut source) {
+            eprintln!("couldn't read the script from stdin: {err}");
+            process::exit(1);
+        }
+
+        if !run_script(&source, cli.mode) {
+            process::exit(1);
+        }
+        return;
+    }
+
+    if !cli.quiet {
+        //greet the user who ran the command by name, if they have one - e.g. a container run with an arbitrary uid might not
+        match get_current_username() {
+            Some(name) => println!(
+                "Hello {:?}! This is the monkey programing language REPL.",
+                name
+            ),
+            None => println!("Hello! This is the monkey programing language REPL."),
+        }
+
+        println!("Feel free to type in some commands.");
+    }
+
+    repl(ReplOptions {
+        history_file: cli.history.or_else(repl::default_history_file),
+        mode: cli.mode,
+    });
+}
+
+//reads each monkey file at the paths and calls the function with its path and source - returns false if any couldn't be read, or the function returned false for any
+fn for_each_file(paths: &[PathBuf], mut f: impl FnMut(&Path, String) -> bool) -> bool {
+    let mut succeeded: bool = true;
+    for path in paths {
+        let files: Vec<PathBuf> = match monkey_files(path) {
+            Ok(files) => files,
+            Err(err) => {
+                eprintln!("couldn't read {}: {err}", path.display());
+                succeeded = false;
+                continue;
+            }
+        };
+
+        for file in files {
+            match fs::read_to_string(&file) {
+                Ok(source) => succeeded &= f(&file, source),
+                Err(err) => {
+                    eprintln!("couldn't read {}: {err}", file.display());
+                    succeeded = false;
+                }
+            }
+        }
+    }
+    succeeded
+}
+
+//what stdin is called in messages about it, in place of a file's path
+const STDIN_NAME: &str = "<stdin>";
+
+//reads all of stdin, for subcommands given no paths
+fn read_stdin() -> Option<String> {
+    let mut source: String = String::new();
+    match io::stdin().read_to_string(&mut source) {
+        Ok(_) => Some(source),
+        Err(err) => {
+            eprintln!("couldn't read stdin: {err}");
+            None
+        }
+    }
+}
+
+//prints the tokens or ast of stdin as json - returns false if stdin couldn't be read, or had errors
+fn emit_json(emit: Emit) -> bool {
+    let Some(source) = read_stdin() else {
+        return false;
+    };
+
+    let json: Value = match emit {
+        Emit::TokensJson => tokens_json(&source),
+        Emit::AstJson => ast_json(&source),
+    };
+    println!("{json:#}");
+    json["errors"].as_array().is_some_and(Vec::is_empty)
+}
+
+//prints the errors that stopped a file being parsed, each as "name:line:column: message"
+fn print_parser_errors(name: &str, source: &str, errors: &[(ParserError, Span)]) {
+    errors.iter().for_each(|(error, span)| {
+        let (line, column) = span.line_column(source);
+        eprintln!("{name}:{line}:{column}: {error}");
+    });
+}
+
+//formats each file in place, or with check, just reports the files that aren't formatted - with no paths, stdin is formatted to stdout.
+//returns false if any file couldn't be read or parsed, or with check, wasn't formatted
+fn fmt(paths: &[PathBuf], check: bool) -> bool {
+    if paths.is_empty() {
+        let Some(source) = read_stdin() else {
+            return false;
+        };
+
+        return match format(&source) {
+            Ok(formatted) if check && formatted != source => {
+                println!("{STDIN_NAME} isn't formatted");
+                false
+            }
+            Ok(_) if check => true,
+            Ok(formatted) => {
+                print!("{formatted}");
+                true
+            }
+            Err(errors) => {
+                print_parser_errors(STDIN_NAME, &source, &errors);
+                false
+            }
+        };
+    }
+
+    for_each_file(paths, |file, source| match format(&source) {
+        Ok(formatted) if formatted == source => true,
+        Ok(_) if check => {
+            println!("{} isn't formatted", file.display());
+            false
+        }
+        Ok(formatted) => match fs::write(file, formatted) {
+            Ok(()) => true,
+            Err(err) => {
+                eprintln!("couldn't write {}: {err}", file.display());
+                false
+            }
+        },
+        Err(errors) => {
+            print_parser_errors(&file.display().to_string(), &source, &errors);
+            false
+        }
+    })
+}
+
+//prints every warning in each file (or stdin with no paths), prefixed by the file it is in - returns false if there were any, or any file couldn't be read or parsed
+fn lint_files(paths: &[PathBuf]) -> bool {
+    let lint_source = |name: &str, source: &str| match lint(source) {
+        Ok(warnings) => {
+            warnings.iter().for_each(|warning: &Warning| {
+                println!("{name}:{}", warning.render(source));
+            });
+            warnings.is_empty()
+        }
+        Err(errors) => {
+            print_parser_errors(name, source, &errors);
+            false
+        }
+    };
+
+    if paths.is_empty() {
+        return read_stdin().is_some_and(|source| lint_source(STDIN_NAME, &source));
     }
 
-    println!("Feel free to type in some commands.");
+    for_each_file(paths, |file, source| {
+        lint_source(&file.display().to_string(), &source)
+    })
+}
+
+//runs the tests in each file, printing whether each passed and where any failed, then how many passed in all - returns false if any failed, or a file couldn't be read, parsed or evaluated
+fn test_files(paths: &[PathBuf]) -> bool {
+    let paths: Vec<PathBuf> = if paths.is_empty() {
+        vec![PathBuf::from(".")]
+    } else {
+        paths.to_vec()
+    };
+
+    let (mut passed, mut failed): (usize, usize) = (0, 0);
+    let succeeded: bool = for_each_file(&paths, |file, source| {
+        let name: String = file.display().to_string();
+        let mut parser: parser::Parser = parser::Parser::new(Lexer::new(&source));
+        let program: Program = parser.parse_program();
+        if !parser.errors().is_empty() {
+            print_parser_errors(&name, &source, &parser.spanned_errors());
+            return false;
+        }
+
+        let results: Vec<TestResult> = match run_tests(&program) {
+            Ok(results) => results,
+            Err(error) => {
+                println!("{name}:{}", error.render(&source));
+                return false;
+            }
+        };
+
+        for result in &results {
+            let (line, column) = result.span.line_column(&source);
+            match &result.error {
+                None => {
+                    println!("test {name}:{line}:{column} {} ... ok", result.name);
+                    passed += 1;
+                }
+                Some(error) => {
+                    println!("test {name}:{line}:{column} {} ... FAILED", result.name);
+                    println!("    {name}:{}", error.render(&source));
+                    failed += 1;
+                }
+            }
+        }
+        results.iter().all(|result| result.error.is_none())
+    });
 
-    repl();
+    let status: &str = if succeeded { "ok" } else { "FAILED" };
+    println!("\ntest result: {status}. {passed} passed; {failed} failed");
+    succeeded
 }
@@ -0,0 +1,302 @@
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::ast::BlockStatement;
+use crate::environment::Environment;
+use crate::token::Span;
+
+//every type of value that monkey code can evaluate to - shared by the tree-walking evaluator and any future vm so both produce the same values
+//functions keep their own copy of their body from the ast, so objects don't borrow the source or the program they came from and can outlive both (e.g. in the repl)
+#[derive(Debug, PartialEq, Clone)]
+pub enum Object {
+    Integer(i32),
+    Boolean(bool),
+    Null,
+    //wraps the value of a return statement, so it can be passed up through any blocks it is in until it reaches the function (or program) it returns from
+    ReturnValue(Box<Object>),
+    Function(Rc<Function>),
+    Builtin(Builtin),
+    //errors are values too, so they can be passed up through the program until something reports them
+    Error(RuntimeError),
+}
+
+impl Object {
+    //the name of the type of object, as used in error messages - e.g. "type mismatch: INTEGER + BOOLEAN"
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Object::Integer(_) => "INTEGER",
+            Object::Boolean(_) => "BOOLEAN",
+            Object::Null => "NULL",
+            Object::ReturnValue(_) => "RETURN_VALUE",
+            Object::Function(_) => "FUNCTION",
+            Object::Builtin(_) => "BUILTIN",
+            Object::Error(_) => "ERROR",
+        }
+    }
+
+    //whether the object counts as true in a condition - only false and null don't (so 0 is truthy)
+    pub fn is_truthy(&self) -> bool {
+        !matches!(self, Object::Boolean(false) | Object::Null)
+    }
+}
+
+impl From<RuntimeError> for Object {
+    fn from(error: RuntimeError) -> Self {
+        Object::Error(error)
+    }
+}
+
+impl fmt::Display for Object {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Object::Integer(value) => write!(f, "{value}"),
+            Object::Boolean(value) => write!(f, "{value}"),
+            Object::Null => write!(f, "null"),
+            Object::ReturnValue(value) => write!(f, "{value}"),
+            Object::Function(function) => write!(f, "{function:?}"),
+            Object::Builtin(builtin) => write!(f, "builtin {}", builtin.name()),
+            Object::Error(error) => write!(f, "ERROR: {error}"),
+        }
+    }
+}
+
+//a function value - it keeps the environment it was created in, so it can still use the variables around it when called later (a closure)
+pub struct Function {
+    pub parameters: Vec<String>,
+    pub body: BlockStatement,
+    pub env: Rc<RefCell<Environment>>,
+}
+
+//the environment isn't printed, as it can contain the function itself
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "fn({}) {{ ... }}", self.parameters.join(", "))
+    }
+}
+
+//two functions are only equal if they are the same function, as comparing their environments could go round in circles
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+        std::ptr::eq(self, other)
+    }
+}
+
+//a function built into the language rather than written in monkey - it can be used anywhere its name isn't bound to something else
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Builtin {
+    //assert(condition) or assert(condition, message) - an error if the condition isn't truthy
+    Assert,
+    //assert_eq(left, right) - an error if the two values aren't equal
+    AssertEq,
+}
+
+impl Builtin {
+    pub const ALL: [Builtin; 2] = [Builtin::Assert, Builtin::AssertEq];
+
+    //the name the builtin is called by in monkey code
+    pub fn name(self) -> &'static str {
+        match self {
+            Builtin::Assert => "assert",
+            Builtin::AssertEq => "assert_eq",
+        }
+    }
+
+    //the builtin with the name, if there is one
+    pub fn lookup(name: &str) -> Option<Builtin> {
+        Builtin::ALL
+            .into_iter()
+            .find(|builtin| builtin.name() == name)
+    }
+
+    //calls the builtin with the arguments given
+    pub fn call(self, arguments: Vec<Object>) -> Result<Object, RuntimeError> {
+        match (self, arguments.as_slice()) {
+            (Builtin::Assert, [condition]) if !condition.is_truthy() => {
+                Err(RuntimeError::new("assertion failed"))
+            }
+            (Builtin::Assert, [condition, message]) if !condition.is_truthy() => {
+                Err(RuntimeError::new(format!("assertion failed: {message}")))
+            }
+            (Builtin::Assert, [_] | [_, _]) => Ok(Object::Null),
+            (Builtin::Assert, _) => Err(RuntimeError::new(format!(
+                "wrong number of arguments: expected 1 or 2, got {}",
+                arguments.len()
+            ))),
+            (Builtin::AssertEq, [left, right]) if left != right => Err(RuntimeError::new(format!(
+                "assertion failed: {left} != {right}"
+            ))),
+            (Builtin::AssertEq, [_, _]) => Ok(Object::Null),
+            (Builtin::AssertEq, _) => Err(RuntimeError::new(format!(
+                "wrong number of arguments: expected 2, got {}",
+                arguments.len()
+            ))),
+        }
+    }
+}
+
+//an error raised while running monkey code (rather than while lexing or parsing it), e.g. dividing by zero
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RuntimeError {
+    pub message: String,
+    //None until whatever raised the error knows where in the source it happened - see with_span()
+    pub span: Option<Span>,
+}
+
+impl RuntimeError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            span: None,
+        }
+    }
+
+    //attaches a source location to the error, keeping any location it already had as that is the more precise one
+    pub fn with_span(mut self, span: Span) -> Self {
+        self.span.get_or_insert(span);
+        self
+    }
+
+    //renders the error with the line and column it happened on, e.g. "2:11: division by zero"
+    pub fn render(&self, source: &str) -> String {
+        match self.span {
+            Some(span) => {
+                let (line, column) = span.line_column(source);
+                format!("{line}:{column}: {}", self.message)
+            }
+            None => self.message.clone(),
+        }
+    }
+
+    fn integer_overflow(left: i32, operator: &str, right: i32) -> Self {
+        Self::new(format!("integer overflow in {left} {operator} {right}"))
+    }
+
+    fn division_by_zero() -> Self {
+        Self::new("division by zero")
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for RuntimeError {}
+
+//integer arithmetic for monkey - rust's i32 operators panic on overflow in debug builds (and always on division by zero), so every evaluator must go through these instead to get a monkey error back
+pub fn checked_add(left: i32, right: i32) -> Result<i32, RuntimeError> {
+    left.checked_add(right)
+        .ok_or_else(|| RuntimeError::integer_overflow(left, "+", right))
+}
+
+pub fn checked_sub(left: i32, right: i32) -> Result<i32, RuntimeError> {
+    left.checked_sub(right)
+        .ok_or_else(|| RuntimeError::integer_overflow(left, "-", right))
+}
+
+pub fn checked_mul(left: i32, right: i32) -> Result<i32, RuntimeError> {
+    left.checked_mul(right)
+        .ok_or_else(|| RuntimeError::integer_overflow(left, "*", right))
+}
+
+//division truncates towards zero, like rust - the only overflowing case is i32::MIN / -1
+pub fn checked_div(left: i32, right: i32) -> Result<i32, RuntimeError> {
+    if right == 0 {
+        return Err(RuntimeError::division_by_zero());
+    }
+
+    left.checked_div(right)
+        .ok_or_else(|| RuntimeError::integer_overflow(left, "/", right))
+}
+
+//the remainder has the sign of the left side, like rust - e.g. -7 % 2 is -1
+pub fn checked_rem(left: i32, right: i32) -> Result<i32, RuntimeError> {
+    if right == 0 {
+        return Err(RuntimeError::division_by_zero());
+    }
+
+    left.checked_rem(right)
+        .ok_or_else(|| RuntimeError::integer_overflow(left, "%", right))
+}
+
+//prefix minus - the only overflowing case is -(i32::MIN)
+pub fn checked_neg(right: i32) -> Result<i32, RuntimeError> {
+    right
+        .checked_neg()
+        .ok_or_else(|| RuntimeError::new(format!("integer overflow in -({right})")))
+}
+
+#[test]
+fn test_checked_arithmetic() {
+    assert_eq!(checked_add(2, 3), Ok(5));
+    assert_eq!(checked_sub(2, 3), Ok(-1));
+    assert_eq!(checked_mul(-4, 3), Ok(-12));
+    assert_eq!(checked_div(7, 2), Ok(3));
+    assert_eq!(checked_div(-7, 2), Ok(-3));
+    assert_eq!(checked_rem(7, 2), Ok(1));
+    assert_eq!(checked_rem(-7, 2), Ok(-1));
+    assert_eq!(checked_neg(5), Ok(-5));
+}
+
+#[test]
+fn test_integer_overflow() {
+    let tests: Vec<(Result<i32, RuntimeError>, &str)> = vec![
+        (
+            checked_add(i32::MAX, 1),
+            "integer overflow in 2147483647 + 1",
+        ),
+        (
+            checked_sub(i32::MIN, 1),
+            "integer overflow in -2147483648 - 1",
+        ),
+        (
+            checked_mul(i32::MAX, 2),
+            "integer overflow in 2147483647 * 2",
+        ),
+        (
+            checked_div(i32::MIN, -1),
+            "integer overflow in -2147483648 / -1",
+        ),
+        (checked_neg(i32::MIN), "integer overflow in -(-2147483648)"),
+    ];
+
+    tests
+        .into_iter()
+        .for_each(|(result, message)| match result {
+            Err(error) => assert_eq!(error.message, message),
+            Ok(value) => panic!("expected \"{message}\", got {value}"),
+        });
+}
+
+#[test]
+fn test_division_by_zero() {
+    assert_eq!(
+        checked_div(1, 0),
+        Err(RuntimeError::new("division by zero"))
+    );
+    assert_eq!(
+        checked_div(0, 0),
+        Err(RuntimeError::new("division by zero"))
+    );
+}
+
+#[test]
+fn test_runtime_error_location() {
+    let source: &str = "let x = 5;\nlet y = x / 0;";
+
+    let error: RuntimeError = checked_div(5, 0).unwrap_err().with_span(Span::new(21, 22));
+    assert_eq!(error.render(source), "2:11: division by zero");
+
+    //the first span attached is kept, as it is closer to where the error happened
+    let error: RuntimeError = error.with_span(Span::new(0, 3));
+    assert_eq!(error.span, Some(Span::new(21, 22)));
+
+    assert_eq!(
+        Object::from(RuntimeError::new("division by zero")),
+        Object::Error(RuntimeError::new("division by zero"))
+    );
+}
@@ -1,10 +1,11 @@
 #![allow(dead_code)]
 #![allow(unused_imports)]
 
-use crate::ast::{Expression, Program, Statement};
-use crate::lexer::Lexer;
-use crate::token::Token;
+use crate::ast::{BlockStatement, Expression, Infix, Prefix, Program, Spanned, Statement};
+use crate::lexer::{Lexer, LexerError, SpannedTokens};
+use crate::token::{Span, Token};
 
+use std::fmt;
 use std::io::Error;
 use std::iter::{Iterator, Peekable};
 
@@ -12,7 +13,8 @@ use std::iter::{Iterator, Peekable};
 
 //[TODO] -  make error system more verbose and thorough
 //accounts for all types of error to be returned, so they can be properly parsed to nice readable errors afterwards
-#[derive(Debug)]
+//if the input ran out before the parser found what it needed, the token given is a Token::EndOfFile
+#[derive(Debug, Clone)]
 pub enum ParserError<'source> {
     ExpectedLet(Token<'source>),
     ExpectedIdentifier(Token<'source>),
@@ -23,6 +25,12 @@ pub enum ParserError<'source> {
     ExpectedRightBrace(Token<'source>),
     ExpectedSemiColon(Token<'source>),
     ExpectedComma(Token<'source>),
+    //the token can't start an expression - e.g. the "*" in "let x = * 5;"
+    ExpectedExpression(Token<'source>),
+    //the left of an "=" isn't a name - e.g. "1 + x = 2"
+    ExpectedAssignable,
+    //the lexer couldn't make a token out of the source - e.g. an integer literal too large for an i32
+    Lexer(LexerError),
     //for any other types of token or edge
     ExpectedToken {
         expected: Token<'source>,
@@ -32,11 +40,98 @@ pub enum ParserError<'source> {
     Unknown(&'source str),
 }
 
+impl<'source> fmt::Display for ParserError<'source> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (expected, got): (&str, &Token) = match self {
+            ParserError::ExpectedLet(got) => ("\"let\"", got),
+            ParserError::ExpectedIdentifier(got) => ("an identifier", got),
+            ParserError::ExpectedAssign(got) => ("\"=\"", got),
+            ParserError::ExpectedLParenthesis(got) => ("\"(\"", got),
+            ParserError::ExpectedRParenthesis(got) => ("\")\"", got),
+            ParserError::ExpectedLeftBrace(got) => ("\"{\"", got),
+            ParserError::ExpectedRightBrace(got) => ("\"}\"", got),
+            ParserError::ExpectedSemiColon(got) => ("\";\"", got),
+            ParserError::ExpectedComma(got) => ("\",\"", got),
+            ParserError::ExpectedExpression(got) => ("an expression", got),
+            ParserError::ExpectedAssignable => return write!(f, "only a name can be assigned to"),
+            ParserError::Lexer(error) => return write!(f, "{error}"),
+            ParserError::ExpectedToken { expected, got } => {
+                return write!(f, "expected {expected}, got {got}")
+            }
+            ParserError::Unknown(context) => return write!(f, "unknown error in {context}"),
+        };
+        write!(f, "expected {expected}, got {got}")
+    }
+}
+
+//how tightly an operator binds to the expressions either side of it - the further down the list, the tighter it binds (e.g. "*" binds tighter than "+", so "1 + 2 * 3" is "1 + (2 * 3)")
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Precedence {
+    Lowest,
+    Assign,      //"="
+    Or,          //"||"
+    And,         //"&&"
+    Equals,      //"==", "!="
+    LessGreater, //"<", ">", "<=", ">="
+    Sum,         //"+", "-"
+    Product,     //"*", "/", "%"
+    Prefix,      //"-x", "!x"
+    Call,        //"add(x)"
+}
+
+impl Precedence {
+    //the precedence of a token when it comes after an expression - anything that can't carry on an expression is the lowest, so parsing stops there
+    pub fn of(token: Token) -> Self {
+        match token {
+            Token::Assign => Precedence::Assign,
+            Token::Or => Precedence::Or,
+            Token::And => Precedence::And,
+            Token::Equal | Token::NotEqual => Precedence::Equals,
+            Token::LessThan
+            | Token::GreaterThan
+            | Token::LessThanEqual
+            | Token::GreaterThanEqual => Precedence::LessGreater,
+            Token::Plus | Token::Minus => Precedence::Sum,
+            Token::Asterisk | Token::Slash | Token::Percent => Precedence::Product,
+            Token::LeftParenthesis => Precedence::Call,
+            _ => Precedence::Lowest,
+        }
+    }
+}
+
+//converts a token into the infix operator it stands for, if it is one
+fn infix_operator(token: Token) -> Option<Infix> {
+    match token {
+        Token::Plus => Some(Infix::Plus),
+        Token::Minus => Some(Infix::Minus),
+        Token::Asterisk => Some(Infix::Asterisk),
+        Token::Slash => Some(Infix::Slash),
+        Token::Percent => Some(Infix::Percent),
+        Token::LessThan => Some(Infix::LessThan),
+        Token::GreaterThan => Some(Infix::GreaterThan),
+        Token::LessThanEqual => Some(Infix::LessThanEqual),
+        Token::GreaterThanEqual => Some(Infix::GreaterThanEqual),
+        Token::Equal => Some(Infix::Equal),
+        Token::NotEqual => Some(Infix::NotEqual),
+        Token::And => Some(Infix::And),
+        Token::Or => Some(Infix::Or),
+        _ => None,
+    }
+}
+
 //a parser that takes the tokenised output from a lexer of a borrow input and turns it into an ast program tree
 pub struct Parser<'source> {
     // input: &'source str,
-    iter: Peekable<Lexer<'source>>,
+    iter: SpannedTokens<'source>,
+    //the next token, once it has been peeked at
+    peeked: Option<(Token<'source>, Span)>,
     errors: Vec<ParserError<'source>>,
+    //where each error in errors was found, in the same order
+    error_spans: Vec<Span>,
+    //the span of the last token read, so the end of the input can be pointed to after it
+    last_span: Span,
+    //how many of the lexer's errors have been added to errors
+    lexer_errors: usize,
 }
 
 impl<'source> Parser<'source> {
@@ -44,14 +139,49 @@ impl<'source> Parser<'source> {
     pub fn new(lexer: Lexer<'source>) -> Self {
         Self {
             // input,
-            iter: lexer.peekable(),
+            iter: lexer.spanned(),
+            peeked: None,
             errors: Vec::new(),
+            error_spans: Vec::new(),
+            last_span: Span::default(),
+            lexer_errors: 0,
         }
     }
 
+    //every error found by parse_program() so far
+    pub fn errors(&self) -> &[ParserError<'source>] {
+        &self.errors
+    }
+
+    //the span of the token each error in errors() was found at, in the same order - for errors at the end of the input, it is the empty span just after the last token
+    pub fn error_spans(&self) -> &[Span] {
+        &self.error_spans
+    }
+
+    //every error along with the span it was found at - for returning the errors once the parser is done with
+    pub fn spanned_errors(&self) -> Vec<(ParserError<'source>, Span)> {
+        self.errors
+            .iter()
+            .cloned()
+            .zip(self.error_spans.iter().copied())
+            .collect()
+    }
+
+    //every error as "line:column: message", for showing to the user - the source must be what the parser was given
+    pub fn render_errors(&self, source: &str) -> Vec<String> {
+        self.errors
+            .iter()
+            .zip(&self.error_spans)
+            .map(|(error, span)| {
+                let (line, column) = span.line_column(source);
+                format!("{line}:{column}: {error}")
+            })
+            .collect()
+    }
+
     //goes through each token in the lexer till it finds a Token::EndOfFile - turning each statement/expression into an ast element, or error, and then adding it to the program struct to be returned.
-    pub fn parse_program(&mut self) -> Program<'source> {
-        let mut program: Program<'source> = Program::new();
+    pub fn parse_program(&mut self) -> Program {
+        let mut program: Program = Program::new();
 
         while let Some(&peek) = self.peek_token() {
             if peek != Token::EndOfFile {
@@ -59,42 +189,91 @@ impl<'source> Parser<'source> {
                     Ok(statement) => program.statements.push(statement),
                     Err(err) => {
                         self.errors.push(err);
+                        let span: Span = self.peek_span();
+                        self.error_spans.push(span);
+                        self.skip_statement();
                     }
                 }
             } else {
                 break;
             }
         }
+        self.add_lexer_errors();
 
         program
     }
 
-    //takes the next token and parses it based on what token is next
-    pub fn parse_statement(&mut self) -> Result<Statement<'source>, ParserError<'source>> {
-        let tok: Option<Token<'source>> = self.read_token();
-        match tok {
-            Some(Token::Let) => self.parse_let_statement(),
-            Some(Token::Return) => self.parse_return_statement(),
-            Some(_) => {
-                //shouldn't error here, and should be replaced with better erroring.
-                println!("statement current: {:?}", tok);
-                println!("statement peek: {:?}", self.peek_token());
-                Err(ParserError::Unknown("parse_statements: Some()"))
-                // self.parse_statement()
-            }
-            None => {
-                println!("None");
-                Err(ParserError::Unknown("parse_statements: None"))
+    //the lexer gives back a Token::Illegal wherever it found an error, so the error the parser found at that token (e.g. "expected an expression") is replaced by the lexer's, which says what was actually wrong.
+    //a lexer error at a token the parser skipped over (e.g. while skipping a broken statement) is added in order of where it was found
+    fn add_lexer_errors(&mut self) {
+        let lexer_errors: Vec<LexerError> = self.iter.errors()[self.lexer_errors..].to_vec();
+        self.lexer_errors += lexer_errors.len();
+
+        for error in lexer_errors {
+            let span: Span = error.span();
+            let at_token: Option<usize> = self.error_spans.iter().position(|&other| other == span);
+            match at_token {
+                Some(i) => self.errors[i] = ParserError::Lexer(error),
+                None => {
+                    let i: usize = self
+                        .error_spans
+                        .partition_point(|error_span| error_span.start <= span.start);
+                    self.errors.insert(i, ParserError::Lexer(error));
+                    self.error_spans.insert(i, span);
+                }
             }
         }
     }
 
+    //takes the next token and parses it based on what token is next - the statement's span doesn't include its semicolon
+    pub fn parse_statement(&mut self) -> Result<Spanned<Statement>, ParserError<'source>> {
+        let start: Span = self.peek_span();
+        let statement: Statement = match self.peek_token().copied() {
+            Some(Token::Let) => {
+                self.read_token();
+                self.parse_let_statement()?
+            }
+            Some(Token::Return) => {
+                self.read_token();
+                self.parse_return_statement()?
+            }
+            Some(_) => self.parse_expression_statement()?,
+            None => return Err(ParserError::Unknown("parse_statements: None")),
+        };
+        let span: Span = self.span_since(start);
+        self.skip_semicolon();
+
+        Ok(Spanned::new(statement, span))
+    }
+
     fn read_token(&mut self) -> Option<Token<'source>> {
-        self.iter.next()
+        let (tok, span) = self.peeked.take().or_else(|| self.iter.next())?;
+        self.last_span = span;
+        Some(tok)
+    }
+
+    fn peek(&mut self) -> Option<&(Token<'source>, Span)> {
+        if self.peeked.is_none() {
+            self.peeked = self.iter.next();
+        }
+        self.peeked.as_ref()
     }
 
     fn peek_token(&mut self) -> Option<&Token<'source>> {
-        self.iter.peek()
+        self.peek().map(|(tok, _)| tok)
+    }
+
+    //the span from the start of the token given to the end of the last token read - e.g. the span of a node that started at that token
+    fn span_since(&self, start: Span) -> Span {
+        Span::new(start.start, self.last_span.end)
+    }
+
+    //errors are always about the next token, so this is where they are found
+    fn peek_span(&mut self) -> Span {
+        match self.peek() {
+            Some(&(_, span)) => span,
+            None => Span::new(self.last_span.end, self.last_span.end),
+        }
     }
 
     //caused lots of issues due to mutable borrows
@@ -106,7 +285,7 @@ impl<'source> Parser<'source> {
     // }
 
     //[TODO?] - make this less of a hellhole? (unclear code, unlogical way of going about it)
-    //checks the next token vs the expected token provided, and if not equal returns error provided
+    //checks the next token vs the expected token provided, and if not equal returns error provided - the token is only read if it was the one expected, so the rest of the statement can be skipped properly after an error
     fn expect_peek<F>(
         &mut self,
         eq: Token<'source>,
@@ -120,16 +299,31 @@ impl<'source> Parser<'source> {
                 self.read_token();
                 Ok(())
             } else {
-                self.read_token();
                 Err(parser_error(peek))
             }
         } else {
-            Err(ParserError::Unknown("expect_peek"))
+            Err(parser_error(Token::EndOfFile))
+        }
+    }
+
+    //semicolons are optional at the end of a statement (e.g. "x + 5" in the repl), so only skip one if it is there
+    fn skip_semicolon(&mut self) {
+        if let Some(Token::Semicolon) = self.peek_token() {
+            self.read_token();
+        }
+    }
+
+    //after an error, skips the rest of the broken statement (up to and including its semicolon) so the parser can carry on from the next one
+    fn skip_statement(&mut self) {
+        while let Some(tok) = self.read_token() {
+            if tok == Token::Semicolon {
+                break;
+            }
         }
     }
 
     //keep incase other method is somehow worse
-    // fn parse_let_statement(&mut self) -> Result<Statement<'source>> {
+    // fn parse_let_statement(&mut self) -> Result<Statement> {
     //     match self.read_token() {
     //         Some(tok) => {
     //             if let Token::Identifier(ident) = tok {
@@ -145,37 +339,255 @@ impl<'source> Parser<'source> {
     //     }
     // }
 
-    fn parse_let_statement(&mut self) -> Result<Statement<'source>, ParserError<'source>> {
-        let identifier: &'source str;
+    fn parse_let_statement(&mut self) -> Result<Statement, ParserError<'source>> {
+        let identifier: Spanned<String> = self.parse_identifier()?;
 
-        if let Some(tok) = self.read_token() {
-            if let Token::Identifier(ident) = tok {
-                identifier = ident;
-            } else {
+        self.expect_peek(Token::Assign, ParserError::ExpectedAssign)?;
+
+        let value: Spanned<Expression> = self.parse_expression(Precedence::Lowest)?;
+
+        Ok(Statement::Let(identifier, value))
+    }
+
+    fn parse_return_statement(&mut self) -> Result<Statement, ParserError<'source>> {
+        let value: Spanned<Expression> = self.parse_expression(Precedence::Lowest)?;
+
+        Ok(Statement::Return(value))
+    }
+
+    fn parse_expression_statement(&mut self) -> Result<Statement, ParserError<'source>> {
+        let expression: Spanned<Expression> = self.parse_expression(Precedence::Lowest)?;
+
+        Ok(Statement::Expression(expression))
+    }
+
+    //parses the statements of a block up to its closing brace - the "{" must already have been read, and is where the block's span starts
+    fn parse_block_statement(&mut self) -> Result<Spanned<BlockStatement>, ParserError<'source>> {
+        let start: Span = self.last_span;
+        let mut block: BlockStatement = BlockStatement::default();
+
+        loop {
+            match self.peek_token().copied() {
+                Some(Token::RightBrace) => {
+                    self.read_token();
+                    return Ok(Spanned::new(block, self.span_since(start)));
+                }
+                None | Some(Token::EndOfFile) => {
+                    return Err(ParserError::ExpectedRightBrace(Token::EndOfFile))
+                }
+                Some(_) => block.statements.push(self.parse_statement()?),
+            }
+        }
+    }
+
+    //reads an identifier, e.g. the name in a let statement or a function parameter
+    fn parse_identifier(&mut self) -> Result<Spanned<String>, ParserError<'source>> {
+        match self.peek_token().copied() {
+            Some(Token::Identifier(ident)) => {
                 self.read_token();
-                return Err(ParserError::ExpectedIdentifier(tok));
+                Ok(Spanned::new(ident.to_string(), self.last_span))
             }
-        } else {
-            return Err(ParserError::Unknown("parse_let_statement"));
+            Some(tok) => Err(ParserError::ExpectedIdentifier(tok)),
+            None => Err(ParserError::ExpectedIdentifier(Token::EndOfFile)),
         }
+    }
 
-        self.expect_peek(Token::Assign, ParserError::ExpectedAssign)?;
+    //parses an expression using pratt parsing - the first token decides what kind of expression it is, and then any operators after it are folded in for as long as they bind tighter than the precedence given.
+    //brackets aren't part of the span of the expression inside them, but are part of the span of any expression they are an operand of - e.g. "(a + b) * c" is all the span of the "*"
+    fn parse_expression(
+        &mut self,
+        precedence: Precedence,
+    ) -> Result<Spanned<Expression>, ParserError<'source>> {
+        //the tokens that end statements and blocks are left for them to deal with
+        let tok: Token<'source> = match self.peek_token().copied() {
+            None | Some(Token::EndOfFile) => {
+                return Err(ParserError::ExpectedExpression(Token::EndOfFile))
+            }
+            //the token is only read if it can start an expression, so the error points at it
+            Some(
+                tok @ (Token::Identifier(_)
+                | Token::Integer(_)
+                | Token::True
+                | Token::False
+                | Token::Bang
+                | Token::Minus
+                | Token::LeftParenthesis
+                | Token::If
+                | Token::Function),
+            ) => {
+                self.read_token();
+                tok
+            }
+            Some(tok) => return Err(ParserError::ExpectedExpression(tok)),
+        };
 
-        //[TODO] - instead of just skipping till finding a semicolon, parse the expression
-        while Some(Token::Semicolon) != self.read_token() {}
+        let start: Span = self.last_span;
+        let mut left: Spanned<Expression> = self.parse_prefix(tok)?;
 
-        Ok(Statement::Let(identifier, Expression::Identifier("hii")))
+        while let Some(&peek) = self.peek_token() {
+            if precedence >= Precedence::of(peek) {
+                break;
+            }
+            //checked before the "=" is read, so the error points at it
+            if peek == Token::Assign && !matches!(left.node, Expression::Identifier(_)) {
+                return Err(ParserError::ExpectedAssignable);
+            }
+            self.read_token();
+            let expression: Expression = self.parse_infix(peek, left)?;
+            left = Spanned::new(expression, self.span_since(start));
+        }
+
+        Ok(left)
+    }
+
+    //parses the expression started by the token given (which has already been read)
+    fn parse_prefix(
+        &mut self,
+        tok: Token<'source>,
+    ) -> Result<Spanned<Expression>, ParserError<'source>> {
+        let start: Span = self.last_span;
+        let expression: Expression = match tok {
+            Token::Identifier(ident) => Expression::Identifier(ident.to_string()),
+            Token::Integer(value) => Expression::Integer(value),
+            Token::True => Expression::Boolean(true),
+            Token::False => Expression::Boolean(false),
+            Token::Bang => self.parse_prefix_expression(Prefix::Bang)?,
+            Token::Minus => self.parse_prefix_expression(Prefix::Minus)?,
+            Token::LeftParenthesis => return self.parse_grouped_expression(),
+            Token::If => self.parse_if_expression()?,
+            Token::Function => self.parse_function_literal()?,
+            tok => return Err(ParserError::ExpectedExpression(tok)),
+        };
+
+        Ok(Spanned::new(expression, self.span_since(start)))
     }
 
-    fn parse_return_statement(&mut self) -> Result<Statement<'source>, ParserError<'source>> {
-        while Some(Token::Semicolon) != self.read_token() {}
+    //parses the rest of an expression that carries on from the left expression, with the token given (which has already been read) - e.g. the "+ 5" in "x + 5"
+    fn parse_infix(
+        &mut self,
+        tok: Token<'source>,
+        left: Spanned<Expression>,
+    ) -> Result<Expression, ParserError<'source>> {
+        if tok == Token::LeftParenthesis {
+            return self.parse_call_expression(left);
+        }
+        if tok == Token::Assign {
+            return self.parse_assign_expression(left);
+        }
+
+        match infix_operator(tok) {
+            Some(operator) => {
+                let right: Spanned<Expression> = self.parse_expression(Precedence::of(tok))?;
+                Ok(Expression::Infix(operator, Box::new(left), Box::new(right)))
+            }
+            None => Err(ParserError::Unknown("parse_infix")),
+        }
+    }
+
+    //the right of an "=" is parsed at the lowest precedence, so assignments group to the right - e.g. "a = b = 1" is "a = (b = 1)"
+    fn parse_assign_expression(
+        &mut self,
+        left: Spanned<Expression>,
+    ) -> Result<Expression, ParserError<'source>> {
+        let Expression::Identifier(name) = left.node else {
+            return Err(ParserError::ExpectedAssignable);
+        };
+        let value: Spanned<Expression> = self.parse_expression(Precedence::Lowest)?;
+
+        Ok(Expression::Assign(
+            Spanned::new(name, left.span),
+            Box::new(value),
+        ))
+    }
+
+    fn parse_prefix_expression(
+        &mut self,
+        operator: Prefix,
+    ) -> Result<Expression, ParserError<'source>> {
+        let right: Spanned<Expression> = self.parse_expression(Precedence::Prefix)?;
+
+        Ok(Expression::Prefix(operator, Box::new(right)))
+    }
+
+    //brackets just change the order things are parsed in, so they don't need their own ast node
+    fn parse_grouped_expression(&mut self) -> Result<Spanned<Expression>, ParserError<'source>> {
+        let expression: Spanned<Expression> = self.parse_expression(Precedence::Lowest)?;
+        self.expect_peek(Token::RightParenthesis, ParserError::ExpectedRParenthesis)?;
+
+        Ok(expression)
+    }
+
+    fn parse_if_expression(&mut self) -> Result<Expression, ParserError<'source>> {
+        self.expect_peek(Token::LeftParenthesis, ParserError::ExpectedLParenthesis)?;
+        let condition: Spanned<Expression> = self.parse_expression(Precedence::Lowest)?;
+        self.expect_peek(Token::RightParenthesis, ParserError::ExpectedRParenthesis)?;
+
+        self.expect_peek(Token::LeftBrace, ParserError::ExpectedLeftBrace)?;
+        let consequence: Spanned<BlockStatement> = self.parse_block_statement()?;
+
+        let alternative: Option<Spanned<BlockStatement>> =
+            if let Some(Token::Else) = self.peek_token() {
+                self.read_token();
+                self.expect_peek(Token::LeftBrace, ParserError::ExpectedLeftBrace)?;
+                Some(self.parse_block_statement()?)
+            } else {
+                None
+            };
+
+        Ok(Expression::If {
+            condition: Box::new(condition),
+            consequence,
+            alternative,
+        })
+    }
+
+    fn parse_function_literal(&mut self) -> Result<Expression, ParserError<'source>> {
+        self.expect_peek(Token::LeftParenthesis, ParserError::ExpectedLParenthesis)?;
+
+        let mut parameters: Vec<Spanned<String>> = Vec::new();
+        if let Some(Token::RightParenthesis) = self.peek_token() {
+            self.read_token();
+        } else {
+            parameters.push(self.parse_identifier()?);
+            while let Some(Token::Comma) = self.peek_token() {
+                self.read_token();
+                parameters.push(self.parse_identifier()?);
+            }
+            self.expect_peek(Token::RightParenthesis, ParserError::ExpectedRParenthesis)?;
+        }
+
+        self.expect_peek(Token::LeftBrace, ParserError::ExpectedLeftBrace)?;
+        let body: Spanned<BlockStatement> = self.parse_block_statement()?;
+
+        Ok(Expression::Function { parameters, body })
+    }
+
+    //parses the arguments of a call - the "(" has already been read
+    fn parse_call_expression(
+        &mut self,
+        function: Spanned<Expression>,
+    ) -> Result<Expression, ParserError<'source>> {
+        let mut arguments: Vec<Spanned<Expression>> = Vec::new();
+        if let Some(Token::RightParenthesis) = self.peek_token() {
+            self.read_token();
+        } else {
+            arguments.push(self.parse_expression(Precedence::Lowest)?);
+            while let Some(Token::Comma) = self.peek_token() {
+                self.read_token();
+                arguments.push(self.parse_expression(Precedence::Lowest)?);
+            }
+            self.expect_peek(Token::RightParenthesis, ParserError::ExpectedRParenthesis)?;
+        }
 
-        Ok(Statement::Return(Expression::Integer(69420)))
+        Ok(Expression::Call {
+            function: Box::new(function),
+            arguments,
+        })
     }
 
     //[TODO] - parse the error enums into actual readable error messages
     //prints out out the errors from a parser
-    fn check_parser_errors(&self) {
+    pub fn check_parser_errors(&self) {
         if self.errors.is_empty() {
             return;
         }
@@ -201,7 +613,7 @@ fn visible_test_parser() {
     program
         .statements
         .into_iter()
-        .for_each(|statement: Statement| {
+        .for_each(|statement: Spanned<Statement>| {
             println!("{:?}", statement);
         })
 }
@@ -244,8 +656,8 @@ fn test_let_statements() {
             .into_iter()
             .enumerate()
             .for_each(|statement: (usize, &str)| {
-                if let Some(Statement::Let(x, _)) = Some(program.statements[statement.0]) {
-                    assert_eq!(x, statement.1);
+                if let Some(Statement::Let(x, _)) = Some(&program.statements[statement.0].node) {
+                    assert_eq!(x.node, statement.1);
                 } else {
                     panic!(
                         "{}: did not recieve a let statement, instead got {:?}",
@@ -280,6 +692,77 @@ fn test_errors() {
     println!("{:?}", parser.errors)
 }
 
+#[test]
+fn test_error_spans() {
+    let tests: Vec<(&str, &str, &str)> = vec![
+        ("let x 5;", "expected \"=\", got the integer 5", "5"),
+        ("let = 5;", "expected an identifier, got \"=\"", "="),
+        ("let x = * 5;", "expected an expression, got \"*\"", "*"),
+        (
+            "if (x) { 1 ",
+            "expected \"}\", got the end of the input",
+            "",
+        ),
+        ("add(1 2)", "expected \")\", got the integer 2", "2"),
+        ("1 + x = 2", "only a name can be assigned to", "="),
+        ("f() = 2", "only a name can be assigned to", "="),
+    ];
+
+    tests.into_iter().for_each(|(input, message, text)| {
+        let mut parser: Parser = Parser::new(Lexer::new(input));
+        parser.parse_program();
+
+        assert_eq!(parser.errors().len(), 1, "{input}");
+        assert_eq!(parser.errors()[0].to_string(), message, "{input}");
+        let span: Span = parser.error_spans()[0];
+        assert_eq!(&input[span.start..span.end], text, "{input}");
+    });
+
+    //an error at the end of the input is just after the last token
+    let mut parser: Parser = Parser::new(Lexer::new("let x ="));
+    parser.parse_program();
+    assert_eq!(parser.error_spans(), &[Span::new(7, 7)]);
+}
+
+//the lexer's errors are reported in place of what the parser made of the illegal token it gave back, or in order if the parser skipped over it
+#[test]
+fn test_lexer_errors() {
+    let tests: Vec<(&str, Vec<(&str, &str)>)> = vec![
+        (
+            "let x = 2147483648;",
+            vec![("integer literal 2147483648 is too large", "2147483648")],
+        ),
+        (
+            "let = 2147483648; let y = 99999999999",
+            vec![
+                ("expected an identifier, got \"=\"", "="),
+                ("integer literal 2147483648 is too large", "2147483648"),
+                ("integer literal 99999999999 is too large", "99999999999"),
+            ],
+        ),
+        (
+            "let x = 1;\nx + /* never /* closed */",
+            vec![("unterminated block comment starting at 2:5", "/*")],
+        ),
+    ];
+
+    tests.into_iter().for_each(|(input, expected)| {
+        let mut parser: Parser = Parser::new(Lexer::new(input));
+        parser.parse_program();
+
+        let errors: Vec<(String, &str)> = parser
+            .spanned_errors()
+            .into_iter()
+            .map(|(error, span)| (error.to_string(), &input[span.start..span.end]))
+            .collect();
+        let expected: Vec<(String, &str)> = expected
+            .into_iter()
+            .map(|(message, text)| (message.to_string(), text))
+            .collect();
+        assert_eq!(errors, expected, "{input}");
+    });
+}
+
 #[test]
 fn test_return_statements() {
     let input: &str = "return 5;
@@ -302,10 +785,12 @@ fn test_return_statements() {
             .into_iter()
             .enumerate()
             .for_each(|statement: (usize, i32)| {
-                if let Some(Statement::Return(Expression::Integer(x))) =
-                    Some(program.statements[statement.0])
+                if let Some(Statement::Return(Spanned {
+                    node: Expression::Integer(x),
+                    ..
+                })) = Some(&program.statements[statement.0].node)
                 {
-                    assert_eq!(x, statement.1);
+                    assert_eq!(*x, statement.1);
                 } else {
                     panic!(
                         "{}: did not recieve a return statement, instead got {:?}",
@@ -321,3 +806,429 @@ fn test_return_statements() {
         );
     }
 }
+
+//parses a single expression statement, failing the test if there were any errors
+fn parse_expression_input(input: &str) -> Expression {
+    let lexer: Lexer = Lexer::new(input);
+    let mut parser: Parser = Parser::new(lexer);
+
+    let mut program: Program = parser.parse_program();
+    parser.check_parser_errors();
+    assert!(parser.errors().is_empty(), "{input}: parser had errors");
+
+    match program.statements.pop() {
+        Some(Spanned {
+            node: Statement::Expression(expression),
+            ..
+        }) if program.statements.is_empty() => expression.node,
+        statement => panic!("{input}: expected one expression statement, got {statement:?}"),
+    }
+}
+
+fn identifier(name: &str) -> Expression {
+    Expression::Identifier(name.to_string())
+}
+
+fn infix(operator: Infix, left: Expression, right: Expression) -> Expression {
+    Expression::Infix(operator, Box::new(left.into()), Box::new(right.into()))
+}
+
+fn prefix(operator: Prefix, right: Expression) -> Expression {
+    Expression::Prefix(operator, Box::new(right.into()))
+}
+
+fn assign(name: &str, value: Expression) -> Expression {
+    Expression::Assign(name.to_string().into(), Box::new(value.into()))
+}
+
+//a let statement with empty spans, to compare with a parsed one
+fn let_statement(name: &str, value: Expression) -> Spanned<Statement> {
+    Statement::Let(name.to_string().into(), value.into()).into()
+}
+
+fn block(statements: Vec<Statement>) -> Spanned<BlockStatement> {
+    BlockStatement {
+        statements: statements.into_iter().map(Spanned::from).collect(),
+    }
+    .into()
+}
+
+#[test]
+fn test_let_statement_values() {
+    let input: &str = "let x = 5;
+    let y = true;
+    let foobar = y;";
+
+    let lexer: Lexer = Lexer::new(input);
+    let mut parser: Parser = Parser::new(lexer);
+
+    let program: Program = parser.parse_program();
+    parser.check_parser_errors();
+
+    assert_eq!(
+        program.statements,
+        vec![
+            let_statement("x", Expression::Integer(5)),
+            let_statement("y", Expression::Boolean(true)),
+            let_statement("foobar", identifier("y")),
+        ]
+    );
+}
+
+#[test]
+fn test_prefix_expressions() {
+    use Expression::{Boolean, Integer};
+
+    let tests: Vec<(&str, Expression)> = vec![
+        ("!5;", prefix(Prefix::Bang, Integer(5))),
+        ("-15;", prefix(Prefix::Minus, Integer(15))),
+        ("!true;", prefix(Prefix::Bang, Boolean(true))),
+        (
+            "!!x;",
+            prefix(Prefix::Bang, prefix(Prefix::Bang, identifier("x"))),
+        ),
+    ];
+
+    tests.into_iter().for_each(|(input, expected)| {
+        assert_eq!(parse_expression_input(input), expected, "{input}");
+    });
+}
+
+#[test]
+fn test_infix_expressions() {
+    use Expression::Integer;
+
+    let tests: Vec<(&str, Infix)> = vec![
+        ("5 + 6;", Infix::Plus),
+        ("5 - 6;", Infix::Minus),
+        ("5 * 6;", Infix::Asterisk),
+        ("5 / 6;", Infix::Slash),
+        ("5 % 6;", Infix::Percent),
+        ("5 < 6;", Infix::LessThan),
+        ("5 > 6;", Infix::GreaterThan),
+        ("5 <= 6;", Infix::LessThanEqual),
+        ("5 >= 6;", Infix::GreaterThanEqual),
+        ("5 == 6;", Infix::Equal),
+        ("5 != 6;", Infix::NotEqual),
+        ("5 && 6;", Infix::And),
+        ("5 || 6;", Infix::Or),
+    ];
+
+    tests.into_iter().for_each(|(input, operator)| {
+        assert_eq!(
+            parse_expression_input(input),
+            infix(operator, Integer(5), Integer(6)),
+            "{input}"
+        );
+    });
+}
+
+#[test]
+fn test_operator_precedence() {
+    use Expression::{Boolean, Integer};
+
+    let tests: Vec<(&str, Expression)> = vec![
+        (
+            "-a * b",
+            infix(
+                Infix::Asterisk,
+                prefix(Prefix::Minus, identifier("a")),
+                identifier("b"),
+            ),
+        ),
+        (
+            "a + b - c",
+            infix(
+                Infix::Minus,
+                infix(Infix::Plus, identifier("a"), identifier("b")),
+                identifier("c"),
+            ),
+        ),
+        (
+            "a + b % c",
+            infix(
+                Infix::Plus,
+                identifier("a"),
+                infix(Infix::Percent, identifier("b"), identifier("c")),
+            ),
+        ),
+        (
+            "1 + 2 <= 3 == true",
+            infix(
+                Infix::Equal,
+                infix(
+                    Infix::LessThanEqual,
+                    infix(Infix::Plus, Integer(1), Integer(2)),
+                    Integer(3),
+                ),
+                Boolean(true),
+            ),
+        ),
+        (
+            "a || b && c",
+            infix(
+                Infix::Or,
+                identifier("a"),
+                infix(Infix::And, identifier("b"), identifier("c")),
+            ),
+        ),
+        (
+            "a && b || c && d",
+            infix(
+                Infix::Or,
+                infix(Infix::And, identifier("a"), identifier("b")),
+                infix(Infix::And, identifier("c"), identifier("d")),
+            ),
+        ),
+        (
+            "a == b && c >= d",
+            infix(
+                Infix::And,
+                infix(Infix::Equal, identifier("a"), identifier("b")),
+                infix(Infix::GreaterThanEqual, identifier("c"), identifier("d")),
+            ),
+        ),
+        (
+            "!a || b",
+            infix(
+                Infix::Or,
+                prefix(Prefix::Bang, identifier("a")),
+                identifier("b"),
+            ),
+        ),
+        (
+            "(a || b) && c",
+            infix(
+                Infix::And,
+                infix(Infix::Or, identifier("a"), identifier("b")),
+                identifier("c"),
+            ),
+        ),
+        (
+            "a + add(b * c) * d",
+            infix(
+                Infix::Plus,
+                identifier("a"),
+                infix(
+                    Infix::Asterisk,
+                    Expression::Call {
+                        function: Box::new(identifier("add").into()),
+                        arguments: vec![
+                            infix(Infix::Asterisk, identifier("b"), identifier("c")).into()
+                        ],
+                    },
+                    identifier("d"),
+                ),
+            ),
+        ),
+        //assignment binds loosest of all, and groups to the right
+        (
+            "x = a || b",
+            assign("x", infix(Infix::Or, identifier("a"), identifier("b"))),
+        ),
+        ("a = b = 1", assign("a", assign("b", Integer(1)))),
+        (
+            "1 + (x = 2)",
+            infix(Infix::Plus, Integer(1), assign("x", Integer(2))),
+        ),
+    ];
+
+    tests.into_iter().for_each(|(input, expected)| {
+        assert_eq!(parse_expression_input(input), expected, "{input}");
+    });
+}
+
+#[test]
+fn test_if_expression() {
+    assert_eq!(
+        parse_expression_input("if (x < y) { x } else { y; }"),
+        Expression::If {
+            condition: Box::new(infix(Infix::LessThan, identifier("x"), identifier("y")).into()),
+            consequence: block(vec![Statement::Expression(identifier("x").into())]),
+            alternative: Some(block(vec![Statement::Expression(identifier("y").into())])),
+        }
+    );
+
+    assert_eq!(
+        parse_expression_input("if (x) { }"),
+        Expression::If {
+            condition: Box::new(identifier("x").into()),
+            consequence: block(vec![]),
+            alternative: None,
+        }
+    );
+}
+
+#[test]
+fn test_function_literals_and_calls() {
+    use Expression::Integer;
+
+    assert_eq!(
+        parse_expression_input("fn(x, y) { return x + y; }"),
+        Expression::Function {
+            parameters: vec![String::from("x").into(), String::from("y").into()],
+            body: block(vec![Statement::Return(
+                infix(Infix::Plus, identifier("x"), identifier("y")).into()
+            )]),
+        }
+    );
+
+    assert_eq!(
+        parse_expression_input("fn() { }()"),
+        Expression::Call {
+            function: Box::new(
+                Expression::Function {
+                    parameters: vec![],
+                    body: block(vec![]),
+                }
+                .into()
+            ),
+            arguments: vec![],
+        }
+    );
+
+    assert_eq!(
+        parse_expression_input("add(1, 2 * 3, -x)"),
+        Expression::Call {
+            function: Box::new(identifier("add").into()),
+            arguments: vec![
+                Integer(1).into(),
+                infix(Infix::Asterisk, Integer(2), Integer(3)).into(),
+                prefix(Prefix::Minus, identifier("x")).into(),
+            ],
+        }
+    );
+}
+
+#[test]
+fn test_error_recovery() {
+    let input: &str = "let x 5;
+    let = 10;
+    let y = * 2;
+    let z = 3;";
+
+    let lexer: Lexer = Lexer::new(input);
+    let mut parser: Parser = Parser::new(lexer);
+
+    let program: Program = parser.parse_program();
+
+    assert_eq!(
+        program.statements,
+        vec![let_statement("z", Expression::Integer(3))]
+    );
+    assert!(matches!(
+        parser.errors(),
+        [
+            ParserError::ExpectedAssign(Token::Integer(5)),
+            ParserError::ExpectedIdentifier(Token::Assign),
+            ParserError::ExpectedExpression(Token::Asterisk),
+        ]
+    ));
+}
+
+#[test]
+fn test_unfinished_input_errors() {
+    let tests: Vec<&str> = vec!["let x = ", "fn(x) { x", "add(1, 2", "if (x"];
+
+    tests.into_iter().for_each(|input| {
+        let lexer: Lexer = Lexer::new(input);
+        let mut parser: Parser = Parser::new(lexer);
+        parser.parse_program();
+
+        match parser.errors() {
+            [ParserError::ExpectedExpression(Token::EndOfFile)]
+            | [ParserError::ExpectedRightBrace(Token::EndOfFile)]
+            | [ParserError::ExpectedRParenthesis(Token::EndOfFile)] => {}
+            errors => panic!("{input}: expected an end of file error, got {errors:?}"),
+        }
+    });
+}
+
+#[test]
+fn test_spans() {
+    let input: &str = "let x = (a + b) * -c;\nif (x) { f(x, 1) } else { return fn(y) { y }; }";
+    let mut parser: Parser = Parser::new(Lexer::new(input));
+    let program: Program = parser.parse_program();
+    assert!(parser.errors().is_empty());
+    let text = |span: Span| &input[span.start..span.end];
+
+    let [first, second] = &program.statements[..] else {
+        panic!("expected two statements, got {:?}", program.statements);
+    };
+    assert_eq!(text(first.span), "let x = (a + b) * -c");
+    let Statement::Let(name, value) = &first.node else {
+        panic!("expected a let statement, got {first:?}");
+    };
+    assert_eq!(text(name.span), "x");
+    assert_eq!(text(value.span), "(a + b) * -c");
+    let Expression::Infix(_, left, right) = &value.node else {
+        panic!("expected an infix expression, got {value:?}");
+    };
+    assert_eq!(text(left.span), "a + b");
+    assert_eq!(text(right.span), "-c");
+
+    assert_eq!(
+        text(second.span),
+        "if (x) { f(x, 1) } else { return fn(y) { y }; }"
+    );
+    let Statement::Expression(Spanned {
+        node:
+            Expression::If {
+                consequence,
+                alternative: Some(alternative),
+                ..
+            },
+        ..
+    }) = &second.node
+    else {
+        panic!("expected an if expression, got {second:?}");
+    };
+    assert_eq!(text(consequence.span), "{ f(x, 1) }");
+    assert_eq!(text(consequence.statements[0].span), "f(x, 1)");
+    assert_eq!(text(alternative.statements[0].span), "return fn(y) { y }");
+}
+
+#[cfg(test)]
+proptest::proptest! {
+    //however the source is written, the span of every expression is the source of just that expression
+    #[test]
+    fn test_expression_spans(program in crate::ast::strategies::program()) {
+        use crate::ast::Visitor;
+
+        struct Expressions<'ast>(Vec<&'ast Spanned<Expression>>);
+
+        impl<'ast> Visitor<'ast> for Expressions<'ast> {
+            fn visit_expression(&mut self, expression: &'ast Spanned<Expression>) {
+                self.0.push(expression);
+                self.walk_expression(expression);
+            }
+        }
+
+        for source in [program.to_string(), format!("{program:#}")] {
+            let mut parser: Parser = Parser::new(Lexer::new(&source));
+            let parsed: Program = parser.parse_program();
+            let mut expressions: Expressions = Expressions(Vec::new());
+            expressions.visit_program(&parsed);
+
+            for expression in expressions.0 {
+                let text: &str = &source[expression.span.start..expression.span.end];
+                proptest::prop_assert_eq!(&parse_expression_input(text), &expression.node, "{} in {}", text, source);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_render_errors() {
+    let input: &str = "let x = 5;\nlet = 2147483648;";
+    let mut parser: Parser = Parser::new(Lexer::new(input));
+    parser.parse_program();
+
+    assert_eq!(
+        parser.render_errors(input),
+        vec![
+            "2:5: expected an identifier, got \"=\"",
+            "2:7: integer literal 2147483648 is too large",
+        ]
+    );
+}
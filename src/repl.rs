@@ -1,30 +1,163 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::ops::ControlFlow;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use clap::ValueEnum;
 use rustyline::error::ReadlineError;
-use rustyline::Editor;
+use rustyline::{Config, Editor};
 
-use crate::lexer::Lexer;
+use crate::ast::{Program, Statement};
+use crate::environment::Environment;
+use crate::evaluator::eval_program;
+use crate::lexer::{Lexer, LexerError};
+use crate::object::Object;
+use crate::parser::Parser;
 use crate::token::Token;
 
+mod command;
+mod helper;
+
+use command::Command;
+use helper::ReplHelper;
+
+const PROMPT: &str = ">> ";
+//shown instead of the normal prompt while the input so far isn't a complete program
+const CONTINUATION_PROMPT: &str = ".. ";
+
+//the most entries kept in the history - the oldest are dropped first
+const HISTORY_SIZE: usize = 1000;
+
+//what the repl does with each input - switched with the ":mode" command
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default, ValueEnum)]
+pub enum Mode {
+    //print the tokens the input is lexed into - useful for debugging the lexer
+    Tokens,
+    //print the syntax tree the input is parsed into - useful for debugging the parser
+    Ast,
+    //evaluate the input and print the result
+    #[default]
+    Eval,
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name: &str = match self {
+            Mode::Tokens => "tokens",
+            Mode::Ast => "ast",
+            Mode::Eval => "eval",
+        };
+        write!(f, "{name}")
+    }
+}
+
+//the settings a repl is started with
+#[derive(Debug, Default)]
+pub struct ReplOptions {
+    //where history is loaded from at the start and saved to at the end - None only keeps it for the session
+    pub history_file: Option<PathBuf>,
+    //what is done with each input until changed with ":mode"
+    pub mode: Mode,
+}
+
+//where history is kept if no other file is given - "monkey/history" in the user's data directory (e.g. ~/.local/share on linux)
+pub fn default_history_file() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("monkey").join("history"))
+}
+
+//creates an editor with history limited to HISTORY_SIZE entries, where an entry the same as the one before it isn't added again
+fn history_editor() -> Editor<ReplHelper> {
+    let config: Config = Config::builder()
+        .max_history_size(HISTORY_SIZE)
+        .history_ignore_dups(true)
+        .build();
+
+    Editor::<ReplHelper>::with_config(config)
+}
+
+//loads history from the file given - it not existing yet (e.g. the first time the repl is run) isn't an error
+fn load_history(rl: &mut Editor<ReplHelper>, path: &Path) -> Result<(), ReadlineError> {
+    match rl.load_history(path) {
+        Err(ReadlineError::Io(err)) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+//saves history to the file given, creating the directory it is in if needed
+fn save_history(rl: &mut Editor<ReplHelper>, path: &Path) -> Result<(), ReadlineError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    rl.save_history(path)
+}
+
 //creates a REPL (read, evaluate, print, loop) instance for executing monkey code.
-pub fn repl() {
-    let mut rl: Editor<()> = Editor::<()>::new();
+pub fn repl(options: ReplOptions) {
+    //the environment is kept between inputs, so names bound in one can be used in the next
+    let env: Rc<RefCell<Environment>> = Rc::new(RefCell::new(Environment::new()));
+    let mut mode: Mode = options.mode;
+
+    let mut rl: Editor<ReplHelper> = history_editor();
+    rl.set_helper(Some(ReplHelper::new(Rc::clone(&env))));
+
+    if let Some(path) = &options.history_file {
+        if let Err(err) = load_history(&mut rl, path) {
+            eprintln!("couldn't load history from {}: {err}", path.display());
+        }
+    }
+
+    //every line typed since the last complete program - code can be split over multiple lines, e.g. the body of a function
+    let mut input: String = String::new();
 
     //loop until error or program is force closed.
     loop {
-        let readline: Result<String, ReadlineError> = rl.readline(">> ");
+        let prompt: &str = if input.is_empty() {
+            PROMPT
+        } else {
+            CONTINUATION_PROMPT
+        };
+
+        if let Some(helper) = rl.helper_mut() {
+            helper.pending.clone_from(&input);
+        }
+
+        let readline: Result<String, ReadlineError> = rl.readline(prompt);
         match readline {
             Ok(line) => {
-                rl.add_history_entry(line.as_str());
-                let mut lex: Lexer = Lexer::new(&line);
-
-                //[TODO] - once parser is built, parse given code and print output
-                //[TODO] - store variables locally so they can be reused in the repl
-                while let Some(token) = Some(lex.next_token()) {
-                    if token != Token::EndOfFile {
-                        println!("{:?}", token);
-                    } else {
+                input.push_str(&line);
+                input.push('\n');
+
+                //commands for the repl are dealt with before the input is treated as monkey code - they are always one line, whatever code they are given
+                let is_command: bool = command::is_command(&input);
+                if !is_command && is_incomplete(&input) {
+                    continue;
+                }
+
+                rl.add_history_entry(input.trim_end());
+
+                if is_command {
+                    let flow: ControlFlow<()> = match Command::parse(&input) {
+                        Ok(command) => command.run(&env, &mut mode),
+                        Err(message) => {
+                            eprintln!("{message}");
+                            ControlFlow::Continue(())
+                        }
+                    };
+                    if flow.is_break() {
                         break;
                     }
+                } else {
+                    run_input(&input, &env, mode);
                 }
+
+                input.clear();
+            }
+            //while part way through some input, CTRL-C only throws that input away
+            Err(ReadlineError::Interrupted) if !input.is_empty() => {
+                input.clear();
             }
             Err(ReadlineError::Interrupted) => {
                 println!("CTRL-C");
@@ -41,4 +174,223 @@ pub fn repl() {
             }
         }
     }
+
+    if let Some(path) = &options.history_file {
+        if let Err(err) = save_history(&mut rl, path) {
+            eprintln!("couldn't save history to {}: {err}", path.display());
+        }
+    }
+}
+
+//runs the whole of the source as a script, e.g. when input is piped in rather than typed - does what the mode says with it, like the repl would with a single input.
+//returns false if it couldn't be lexed or parsed, or evaluating it gave an error
+pub fn run_script(source: &str, mode: Mode) -> bool {
+    let env: Rc<RefCell<Environment>> = Rc::new(RefCell::new(Environment::new()));
+    run_input(source, &env, mode)
+}
+
+//does what the mode says with the input - returns false if something went wrong
+fn run_input(input: &str, env: &Rc<RefCell<Environment>>, mode: Mode) -> bool {
+    match mode {
+        Mode::Tokens => print_tokens(input),
+        Mode::Ast => print_ast(input),
+        Mode::Eval => eval_input(input, env),
+    }
+}
+
+//prints every token the input is lexed into, one per line - returns false if the lexer found any errors
+fn print_tokens(input: &str) -> bool {
+    let mut lex: Lexer = Lexer::new(input);
+    lex.by_ref().for_each(|token| println!("{token:?}"));
+    lex.errors().is_empty()
+}
+
+//prints the syntax tree the input is parsed into, along with any errors found parsing it - returns false if there were any
+fn print_ast(input: &str) -> bool {
+    let mut parser: Parser = Parser::new(Lexer::new(input));
+    let program: Program = parser.parse_program();
+    print_parser_errors(&parser, input);
+    println!("{program:#?}");
+    parser.errors().is_empty()
+}
+
+//parses and evaluates the input in the repl's environment, and prints the result - returns false if it couldn't be parsed or evaluated to an error
+fn eval_input(input: &str, env: &Rc<RefCell<Environment>>) -> bool {
+    let mut parser: Parser = Parser::new(Lexer::new(input));
+    let program: Program = parser.parse_program();
+    if !parser.errors().is_empty() {
+        print_parser_errors(&parser, input);
+        return false;
+    }
+
+    let result: Object = eval_program(&program, env);
+    if let Object::Error(error) = &result {
+        println!("ERROR: {}", error.render(input));
+        return false;
+    }
+
+    //let statements don't have a value worth printing
+    let last: Option<&Statement> = program.statements.last().map(|statement| &statement.node);
+    if !matches!(last, Some(Statement::Let(..))) {
+        println!("{result}");
+    }
+    true
+}
+
+//prints every error the parser found in the input, the same way as errors from evaluating it
+fn print_parser_errors(parser: &Parser, input: &str) {
+    parser
+        .render_errors(input)
+        .iter()
+        .for_each(|error| println!("ERROR: {error}"));
+}
+
+//checks whether the input needs more lines before it can be a complete program - if it has brackets or braces that haven't been closed, a block comment that hasn't been closed, or ends with an operator or keyword that needs something after it.
+//input with too many closing brackets isn't incomplete, as more input can't fix it - the parser reports the error instead
+pub fn is_incomplete(input: &str) -> bool {
+    let mut lex: Lexer = Lexer::new(input);
+    let mut depth: isize = 0;
+    let mut last: Token = Token::EndOfFile;
+
+    loop {
+        let token: Token = lex.next_token();
+        match token {
+            Token::EndOfFile => break,
+            Token::LeftParenthesis | Token::LeftBrace => depth += 1,
+            Token::RightParenthesis | Token::RightBrace => depth -= 1,
+            _ => {}
+        }
+        last = token;
+    }
+
+    if lex
+        .errors()
+        .iter()
+        .any(|error| matches!(error, LexerError::UnterminatedBlockComment(..)))
+    {
+        return true;
+    }
+
+    depth > 0
+        || matches!(
+            last,
+            Token::Assign
+                | Token::Plus
+                | Token::Minus
+                | Token::Bang
+                | Token::Asterisk
+                | Token::Slash
+                | Token::Percent
+                | Token::LessThan
+                | Token::GreaterThan
+                | Token::LessThanEqual
+                | Token::GreaterThanEqual
+                | Token::Equal
+                | Token::NotEqual
+                | Token::And
+                | Token::Or
+                | Token::Comma
+                | Token::Function
+                | Token::Let
+                | Token::If
+                | Token::Else
+                | Token::Return
+        )
+}
+
+#[test]
+fn test_history_file() {
+    let dir: PathBuf = std::env::temp_dir().join(format!("monkey-history-{}", std::process::id()));
+    let path: PathBuf = dir.join("nested").join("history");
+
+    let mut rl: Editor<ReplHelper> = history_editor();
+    //loading a file that doesn't exist yet is fine
+    load_history(&mut rl, &path).unwrap();
+
+    ["let x = 5;", "x", "x", "let f = fn(a) {\n  a\n};", "x"]
+        .into_iter()
+        .for_each(|entry| {
+            rl.add_history_entry(entry);
+        });
+    save_history(&mut rl, &path).unwrap();
+
+    let mut rl: Editor<ReplHelper> = history_editor();
+    load_history(&mut rl, &path).unwrap();
+    let entries: Vec<&String> = rl.history().iter().collect();
+    fs::remove_dir_all(&dir).unwrap();
+
+    //only the repeat straight after an entry is dropped, and multi-line entries stay whole
+    assert_eq!(
+        entries,
+        vec!["let x = 5;", "x", "let f = fn(a) {\n  a\n};", "x"]
+    );
+}
+
+#[test]
+fn test_history_size() {
+    let mut rl: Editor<ReplHelper> = history_editor();
+    (0..HISTORY_SIZE + 10).for_each(|i| {
+        rl.add_history_entry(i.to_string());
+    });
+
+    assert_eq!(rl.history().len(), HISTORY_SIZE);
+    assert_eq!(rl.history().get(0), Some(&String::from("10")));
+}
+
+#[test]
+fn test_run_script() {
+    let tests: Vec<(&str, Mode, bool)> = vec![
+        ("let x = 5;\nlet y = x * 2;\ny", Mode::Eval, true),
+        ("let x = 5;", Mode::Eval, true),
+        ("1 / 0", Mode::Eval, false),
+        ("let = 5;", Mode::Eval, false),
+        ("let = 5;", Mode::Tokens, true),
+        ("x /* unterminated", Mode::Tokens, false),
+        ("-a * b", Mode::Ast, true),
+        ("let = 5;", Mode::Ast, false),
+    ];
+
+    tests.into_iter().for_each(|(source, mode, expected)| {
+        assert_eq!(run_script(source, mode), expected, "{source:?} in {mode}");
+    });
+}
+
+#[test]
+fn test_mode_names() {
+    [Mode::Tokens, Mode::Ast, Mode::Eval]
+        .into_iter()
+        .for_each(|mode| {
+            assert_eq!(Mode::from_str(&mode.to_string(), true), Ok(mode));
+        });
+
+    assert_eq!(Mode::default(), Mode::Eval);
+}
+
+#[test]
+fn test_is_incomplete() {
+    let tests: Vec<(&str, bool)> = vec![
+        ("", false),
+        ("let x = 5;", false),
+        ("x + y", false),
+        ("let add = fn(x, y) {", true),
+        ("let add = fn(x, y) {\n  x + y\n", true),
+        ("let add = fn(x, y) {\n  x + y\n};", false),
+        ("add(1,", true),
+        ("add(1,\n 2)", false),
+        ("if (x) { 1 } else", true),
+        ("let x =", true),
+        ("5 *", true),
+        ("a &&", true),
+        ("return", true),
+        ("x /* a comment", true),
+        ("x /* a comment */", false),
+        ("x // a comment", false),
+        ("let x = 5; // (", false),
+        //closing too many can't be fixed by adding more lines
+        ("x })", false),
+    ];
+
+    tests.into_iter().for_each(|(input, expected)| {
+        assert_eq!(is_incomplete(input), expected, "{input:?}");
+    });
 }
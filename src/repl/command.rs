@@ -0,0 +1,208 @@
+use std::cell::RefCell;
+use std::fs;
+use std::ops::ControlFlow;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+
+use crate::ast::Program;
+use crate::environment::Environment;
+use crate::evaluator::eval_program;
+use crate::lexer::Lexer;
+use crate::object::Object;
+use crate::parser::Parser;
+
+use super::{eval_input, print_ast, print_parser_errors, print_tokens, Mode};
+
+const HELP: &str = "\
+:help           show this message
+:env            list every name bound in the session and its value
+:reset          throw away every binding and start a fresh session
+:load <file>    run a monkey file in the session, keeping anything it binds
+:tokens <code>  print the tokens the code is lexed into
+:ast <code>     print the syntax tree the code is parsed into
+:time <code>    run the code and print how long it took
+:mode [mode]    show the mode, or switch to another - tokens, ast or eval
+:quit           leave the repl";
+
+//a command for the repl itself rather than monkey code - they start with a ":", which can't start any monkey code
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Command<'input> {
+    Help,
+    Env,
+    Reset,
+    Load(&'input str),
+    Tokens(&'input str),
+    Ast(&'input str),
+    Time(&'input str),
+    //None shows the current mode rather than switching
+    Mode(Option<Mode>),
+    Quit,
+}
+
+//checks if the input is a repl command rather than monkey code
+pub fn is_command(input: &str) -> bool {
+    input.trim_start().starts_with(':')
+}
+
+impl<'input> Command<'input> {
+    //parses a command from the input (which must start with a ":") - returns a message to show if it isn't a known command or is missing what it needs
+    pub fn parse(input: &'input str) -> Result<Self, String> {
+        let input: &str = input.trim();
+        let (name, argument) = match input.split_once(char::is_whitespace) {
+            Some((name, argument)) => (name, argument.trim()),
+            None => (input, ""),
+        };
+
+        let needs_argument = |command: fn(&'input str) -> Command<'input>, usage: &str| {
+            if argument.is_empty() {
+                Err(format!("usage: {name} {usage}"))
+            } else {
+                Ok(command(argument))
+            }
+        };
+
+        match name {
+            ":help" | ":h" => Ok(Command::Help),
+            ":env" => Ok(Command::Env),
+            ":reset" => Ok(Command::Reset),
+            ":load" => needs_argument(Command::Load, "<file>"),
+            ":tokens" => needs_argument(Command::Tokens, "<code>"),
+            ":ast" => needs_argument(Command::Ast, "<code>"),
+            ":time" => needs_argument(Command::Time, "<code>"),
+            ":mode" if argument.is_empty() => Ok(Command::Mode(None)),
+            ":mode" => match Mode::from_str(argument, true) {
+                Ok(mode) => Ok(Command::Mode(Some(mode))),
+                Err(_) => Err(format!(
+                    "unknown mode {argument} - expected tokens, ast or eval"
+                )),
+            },
+            ":quit" | ":q" => Ok(Command::Quit),
+            _ => Err(format!(
+                "unknown command {name} - type :help to see every command"
+            )),
+        }
+    }
+
+    //runs the command in the repl's environment and mode - breaks if the repl should stop
+    pub fn run(self, env: &Rc<RefCell<Environment>>, mode: &mut Mode) -> ControlFlow<()> {
+        match self {
+            Command::Help => println!("{HELP}"),
+            Command::Env => print_env(&env.borrow()),
+            //the environment is replaced in place, as the repl's helper also holds on to it
+            Command::Reset => *env.borrow_mut() = Environment::new(),
+            Command::Load(path) => match fs::read_to_string(path) {
+                Ok(source) => {
+                    eval_input(&source, env);
+                }
+                Err(err) => eprintln!("couldn't load {path}: {err}"),
+            },
+            Command::Tokens(code) => {
+                print_tokens(code);
+            }
+            Command::Ast(code) => {
+                print_ast(code);
+            }
+            Command::Time(code) => time(code, env),
+            Command::Mode(None) => println!("{mode}"),
+            Command::Mode(Some(new)) => *mode = new,
+            Command::Quit => return ControlFlow::Break(()),
+        }
+
+        ControlFlow::Continue(())
+    }
+}
+
+//prints every name bound in the environment with its value, in alphabetical order
+fn print_env(env: &Environment) {
+    let names: Vec<String> = env.names();
+    if names.is_empty() {
+        println!("(no bindings)");
+    }
+
+    names.into_iter().for_each(|name| {
+        if let Some(value) = env.get(&name) {
+            println!("{name} = {value}");
+        }
+    });
+}
+
+//runs the code like normal input, but also prints how long it took to run (not including lexing and parsing)
+fn time(code: &str, env: &Rc<RefCell<Environment>>) {
+    let mut parser: Parser = Parser::new(Lexer::new(code));
+    let program: Program = parser.parse_program();
+    if !parser.errors().is_empty() {
+        print_parser_errors(&parser, code);
+        return;
+    }
+
+    let start: Instant = Instant::now();
+    let result: Object = eval_program(&program, env);
+    let elapsed: Duration = start.elapsed();
+
+    println!("{result}");
+    println!("took {elapsed:?}");
+}
+
+#[test]
+fn test_parse_commands() {
+    let tests: Vec<(&str, Result<Command, String>)> = vec![
+        (":help", Ok(Command::Help)),
+        ("  :env  ", Ok(Command::Env)),
+        (":reset", Ok(Command::Reset)),
+        (
+            ":load scripts/fib.monkey",
+            Ok(Command::Load("scripts/fib.monkey")),
+        ),
+        (":tokens let x = 5;", Ok(Command::Tokens("let x = 5;"))),
+        (":tokens (", Ok(Command::Tokens("("))),
+        (":ast fn(x) {", Ok(Command::Ast("fn(x) {"))),
+        (":ast  1 + 2 * 3\n", Ok(Command::Ast("1 + 2 * 3"))),
+        (":time fib(20)", Ok(Command::Time("fib(20)"))),
+        (":mode", Ok(Command::Mode(None))),
+        (":mode AST", Ok(Command::Mode(Some(Mode::Ast)))),
+        (
+            ":mode bytecode",
+            Err(String::from(
+                "unknown mode bytecode - expected tokens, ast or eval",
+            )),
+        ),
+        (":quit", Ok(Command::Quit)),
+        (":load", Err(String::from("usage: :load <file>"))),
+        (
+            ":exit",
+            Err(String::from(
+                "unknown command :exit - type :help to see every command",
+            )),
+        ),
+    ];
+
+    tests.into_iter().for_each(|(input, expected)| {
+        assert!(is_command(input));
+        assert_eq!(Command::parse(input), expected, "{input}");
+    });
+
+    assert!(!is_command("let x = 5;"));
+}
+
+#[test]
+fn test_run_commands() {
+    let env: Rc<RefCell<Environment>> = Rc::new(RefCell::new(Environment::new()));
+    let shared: Rc<RefCell<Environment>> = Rc::clone(&env);
+    env.borrow_mut().set("x", Object::Integer(5));
+
+    let mut mode: Mode = Mode::Eval;
+
+    assert_eq!(
+        Command::Reset.run(&env, &mut mode),
+        ControlFlow::Continue(())
+    );
+    assert_eq!(shared.borrow().get("x"), None);
+    assert_eq!(
+        Command::Mode(Some(Mode::Tokens)).run(&env, &mut mode),
+        ControlFlow::Continue(())
+    );
+    assert_eq!(mode, Mode::Tokens);
+    assert_eq!(Command::Quit.run(&env, &mut mode), ControlFlow::Break(()));
+}
@@ -0,0 +1,409 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use rustyline::completion::{Completer, Pair};
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{Context, Helper};
+
+use crate::environment::Environment;
+use crate::lexer::{self, Lexer, LosslessKind, LosslessLexer, Trivia};
+use crate::object::{Builtin, Object};
+use crate::token::{self, Span, Token};
+
+use super::command;
+
+//ansi colour codes for each kind of token - only the colour changes, so the highlighted line takes up the same width as the original
+const KEYWORD_COLOUR: &str = "\x1b[35m"; //magenta
+const LITERAL_COLOUR: &str = "\x1b[33m"; //yellow
+const OPERATOR_COLOUR: &str = "\x1b[36m"; //cyan
+const COMMENT_COLOUR: &str = "\x1b[90m"; //grey
+const ILLEGAL_COLOUR: &str = "\x1b[4;31m"; //underlined red
+const MATCHING_BRACKET_COLOUR: &str = "\x1b[1;34m"; //bold blue
+const RESET: &str = "\x1b[0m";
+
+//hooks the monkey lexer into rustyline, so the line being typed is highlighted, checked and tab completed as it is typed
+#[derive(Default)]
+pub struct ReplHelper {
+    //the lines typed before this one that are part of the same input, so brackets opened on them can be closed on this one
+    pub pending: String,
+    //the repl's environment, so names bound in it can be completed
+    pub env: Rc<RefCell<Environment>>,
+}
+
+impl ReplHelper {
+    pub fn new(env: Rc<RefCell<Environment>>) -> Self {
+        Self {
+            pending: String::new(),
+            env,
+        }
+    }
+}
+
+impl Helper for ReplHelper {}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        Ok(complete(line, pos, &self.env.borrow()))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {
+    fn highlight<'l>(&self, line: &'l str, pos: usize) -> Cow<'l, str> {
+        //repl commands aren't monkey code, so the ":" would otherwise be shown as illegal
+        if self.pending.is_empty() && command::is_command(line) {
+            return Cow::Borrowed(line);
+        }
+        Cow::Owned(highlight(line, pos))
+    }
+
+    //every char can change how the line is highlighted (e.g. typing the "t" of "let"), and moving the cursor changes which brackets are matched
+    fn highlight_char(&self, _line: &str, _pos: usize) -> bool {
+        true
+    }
+}
+
+impl Validator for ReplHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        //repl commands aren't monkey code, so their brackets don't need to match
+        if self.pending.is_empty() && command::is_command(ctx.input()) {
+            return Ok(ValidationResult::Valid(None));
+        }
+
+        let input: String = format!("{}{}", self.pending, ctx.input());
+
+        Ok(match check_brackets(&input) {
+            Some(message) => ValidationResult::Invalid(Some(format!("  <- {message}"))),
+            None => ValidationResult::Valid(None),
+        })
+    }
+}
+
+//finds the identifier being typed just before the cursor, and every keyword, builtin or bound name it could be the start of - returns where the identifier starts, and the candidates to replace it with.
+//bound functions and builtins are completed with an opening bracket, ready to be called
+pub fn complete(line: &str, pos: usize, env: &Environment) -> (usize, Vec<Pair>) {
+    let before: &str = &line[..pos];
+    let start: usize = before
+        .char_indices()
+        .rev()
+        .take_while(|&(_, ch)| lexer::is_identifier_continue(ch))
+        .last()
+        .map_or(pos, |(index, _)| index);
+    let prefix: &str = &line[start..pos];
+
+    //an identifier can't start with a digit, so there is nothing to complete in e.g. "12"
+    if !prefix.is_empty() && !prefix.starts_with(lexer::is_identifier_start) {
+        return (pos, Vec::new());
+    }
+
+    let keywords = token::KEYWORDS.iter().map(|&(keyword, _)| Pair {
+        display: keyword.to_string(),
+        replacement: keyword.to_string(),
+    });
+    //a builtin hidden by a binding of its name is left to the binding
+    let builtins = Builtin::ALL
+        .into_iter()
+        .filter(|builtin| env.get(builtin.name()).is_none())
+        .map(|builtin| Pair {
+            display: builtin.name().to_string(),
+            replacement: format!("{}(", builtin.name()),
+        });
+    let names = env.names().into_iter().map(|name| {
+        let replacement: String = match env.get(&name) {
+            Some(Object::Function(_)) => format!("{name}("),
+            _ => name.clone(),
+        };
+        Pair {
+            display: name,
+            replacement,
+        }
+    });
+
+    let candidates: Vec<Pair> = keywords
+        .chain(builtins)
+        .chain(names)
+        .filter(|candidate| candidate.display.starts_with(prefix))
+        .collect();
+
+    (start, candidates)
+}
+
+//the colour a token is highlighted in - identifiers, delimiters and whitespace are left as they are
+fn colour(kind: LosslessKind) -> Option<&'static str> {
+    match kind {
+        LosslessKind::Token(token) => match token {
+            Token::Function | Token::Let | Token::If | Token::Else | Token::Return => {
+                Some(KEYWORD_COLOUR)
+            }
+            Token::Integer(_) | Token::True | Token::False => Some(LITERAL_COLOUR),
+            Token::Assign
+            | Token::Plus
+            | Token::Minus
+            | Token::Bang
+            | Token::Asterisk
+            | Token::Slash
+            | Token::Percent
+            | Token::LessThan
+            | Token::GreaterThan
+            | Token::LessThanEqual
+            | Token::GreaterThanEqual
+            | Token::Equal
+            | Token::NotEqual
+            | Token::And
+            | Token::Or => Some(OPERATOR_COLOUR),
+            Token::Illegal => Some(ILLEGAL_COLOUR),
+            _ => None,
+        },
+        LosslessKind::Trivia(Trivia::LineComment | Trivia::BlockComment) => Some(COMMENT_COLOUR),
+        LosslessKind::Trivia(_) => None,
+    }
+}
+
+//returns the line with ansi colour codes added around each token, and around the bracket under (or just before) the cursor and the one it matches
+pub fn highlight(line: &str, pos: usize) -> String {
+    let brackets: Option<(usize, usize)> = matching_brackets(line, pos);
+    let mut output: String = String::with_capacity(line.len());
+
+    for token in LosslessLexer::new(line) {
+        let colour: Option<&str> = match brackets {
+            Some((open, close)) if token.span.start == open || token.span.start == close => {
+                Some(MATCHING_BRACKET_COLOUR)
+            }
+            _ => colour(token.kind),
+        };
+
+        match colour {
+            Some(colour) => {
+                output.push_str(colour);
+                output.push_str(token.text);
+                output.push_str(RESET);
+            }
+            None => output.push_str(token.text),
+        }
+    }
+
+    output
+}
+
+fn is_open_bracket(token: Token) -> bool {
+    matches!(token, Token::LeftParenthesis | Token::LeftBrace)
+}
+
+fn is_close_bracket(token: Token) -> bool {
+    matches!(token, Token::RightParenthesis | Token::RightBrace)
+}
+
+fn brackets_match(open: Token, close: Token) -> bool {
+    matches!(
+        (open, close),
+        (Token::LeftParenthesis, Token::RightParenthesis) | (Token::LeftBrace, Token::RightBrace)
+    )
+}
+
+//every bracket in the input (ignoring any in comments) with where it is
+fn brackets(input: &str) -> Vec<(Token<'_>, Span)> {
+    let mut lex: Lexer = Lexer::new(input);
+    let mut brackets: Vec<(Token, Span)> = Vec::new();
+
+    loop {
+        let (token, span) = lex.next_spanned_token();
+        if token == Token::EndOfFile {
+            return brackets;
+        }
+        if is_open_bracket(token) || is_close_bracket(token) {
+            brackets.push((token, span));
+        }
+    }
+}
+
+//finds the bracket the cursor is on (or, failing that, just after) and the bracket that matches it - returns the byte offsets of the opening and closing bracket
+fn matching_brackets(line: &str, pos: usize) -> Option<(usize, usize)> {
+    let brackets: Vec<(Token, Span)> = brackets(line);
+
+    let index: usize = brackets
+        .iter()
+        .position(|(_, span)| span.start == pos)
+        .or_else(|| brackets.iter().position(|(_, span)| span.end == pos))?;
+    let (bracket, span) = brackets[index];
+
+    //walks away from the bracket (forwards for an opening one, backwards for a closing one) until the brackets in between are balanced
+    let mut depth: usize = 0;
+    if is_open_bracket(bracket) {
+        for &(other, other_span) in &brackets[index + 1..] {
+            if is_open_bracket(other) {
+                depth += 1;
+            } else if depth > 0 {
+                depth -= 1;
+            } else {
+                return brackets_match(bracket, other).then_some((span.start, other_span.start));
+            }
+        }
+    } else {
+        for &(other, other_span) in brackets[..index].iter().rev() {
+            if is_close_bracket(other) {
+                depth += 1;
+            } else if depth > 0 {
+                depth -= 1;
+            } else {
+                return brackets_match(other, bracket).then_some((other_span.start, span.start));
+            }
+        }
+    }
+
+    None
+}
+
+//checks that every closing bracket in the input closes the bracket opened before it - returns a message describing the first one that doesn't.
+//brackets that are still open are fine, as the rest of the input can close them, and so are closing brackets with nothing open - like is_incomplete(), they are left for the parser to report once the input is submitted
+pub fn check_brackets(input: &str) -> Option<String> {
+    let mut open: Vec<Token> = Vec::new();
+
+    for (token, _) in brackets(input) {
+        if is_open_bracket(token) {
+            open.push(token);
+            continue;
+        }
+
+        if let Some(opener) = open.pop() {
+            if !brackets_match(opener, token) {
+                return Some(format!(
+                    "mismatched brackets: {} closed by {}",
+                    bracket_str(opener),
+                    bracket_str(token)
+                ));
+            }
+        }
+    }
+
+    None
+}
+
+fn bracket_str(token: Token) -> &'static str {
+    match token {
+        Token::LeftParenthesis => "\"(\"",
+        Token::RightParenthesis => "\")\"",
+        Token::LeftBrace => "\"{\"",
+        Token::RightBrace => "\"}\"",
+        _ => "bracket",
+    }
+}
+
+#[test]
+fn test_complete() {
+    use crate::evaluator::eval_program;
+    use crate::parser::Parser;
+
+    let env: Rc<RefCell<Environment>> = Rc::new(RefCell::new(Environment::new()));
+    let program = Parser::new(Lexer::new(
+        "let add = fn(x, y) { x + y }; let addend = 2; let café = 3;",
+    ))
+    .parse_program();
+    eval_program(&program, &env);
+
+    let replacements = |line: &str, pos: usize| -> (usize, Vec<String>) {
+        let (start, candidates) = complete(line, pos, &env.borrow());
+        (
+            start,
+            candidates
+                .into_iter()
+                .map(|candidate| candidate.replacement)
+                .collect(),
+        )
+    };
+
+    assert_eq!(
+        replacements("ad", 2),
+        (0, vec![String::from("add("), String::from("addend")])
+    );
+    //part way through an expression, and with the cursor before the end of the line
+    assert_eq!(
+        replacements("let y = 1 + ad(2, 3)", 13),
+        (
+            12,
+            vec![
+                String::from("assert("),
+                String::from("assert_eq("),
+                String::from("add("),
+                String::from("addend")
+            ]
+        )
+    );
+    assert_eq!(replacements("re", 2), (0, vec![String::from("return")]));
+    assert_eq!(
+        replacements("f", 1),
+        (0, vec![String::from("fn"), String::from("false")])
+    );
+    assert_eq!(replacements("x + caf", 7), (4, vec![String::from("café")]));
+    assert_eq!(
+        replacements("as", 2),
+        (0, vec![String::from("assert("), String::from("assert_eq(")])
+    );
+    assert_eq!(replacements("12", 2), (2, vec![]));
+    assert_eq!(replacements("zzz", 3), (0, vec![]));
+}
+
+#[test]
+fn test_highlight() {
+    assert_eq!(
+        highlight("let x = 5; // five", 0),
+        format!(
+            "{KEYWORD_COLOUR}let{RESET} x {OPERATOR_COLOUR}={RESET} {LITERAL_COLOUR}5{RESET}; {COMMENT_COLOUR}// five{RESET}"
+        )
+    );
+    assert_eq!(
+        highlight("true £", 0),
+        format!("{LITERAL_COLOUR}true{RESET} {ILLEGAL_COLOUR}£{RESET}")
+    );
+    assert_eq!(highlight("", 0), "");
+}
+
+#[test]
+fn test_highlight_matching_brackets() {
+    let line: &str = "f(x, (y)) { z }";
+
+    //cursor on the opening bracket
+    assert_eq!(matching_brackets(line, 1), Some((1, 8)));
+    //cursor just after the closing bracket
+    assert_eq!(matching_brackets(line, 9), Some((1, 8)));
+    assert_eq!(matching_brackets(line, 5), Some((5, 7)));
+    assert_eq!(matching_brackets(line, 10), Some((10, 14)));
+    //not on a bracket
+    assert_eq!(matching_brackets(line, 3), None);
+    //brackets in comments are ignored, and mismatched brackets aren't highlighted
+    assert_eq!(matching_brackets("( /* ) */ }", 0), None);
+    assert_eq!(matching_brackets("(", 0), None);
+
+    assert_eq!(
+        highlight("(x)", 0),
+        format!("{MATCHING_BRACKET_COLOUR}({RESET}x{MATCHING_BRACKET_COLOUR}){RESET}")
+    );
+}
+
+#[test]
+fn test_check_brackets() {
+    assert_eq!(check_brackets("fn(x) { x }"), None);
+    assert_eq!(check_brackets("fn(x) {"), None);
+    assert_eq!(check_brackets("// )"), None);
+    assert_eq!(check_brackets("1 )"), None);
+    assert_eq!(
+        check_brackets("(1 })"),
+        Some(String::from("mismatched brackets: \"(\" closed by \"}\""))
+    );
+    assert_eq!(
+        check_brackets("fn(x} {"),
+        Some(String::from("mismatched brackets: \"(\" closed by \"}\""))
+    );
+}
@@ -0,0 +1,160 @@
+use std::fmt::Write;
+
+use crate::ast::{BlockStatement, Expression, Program, Spanned, Statement};
+use crate::lexer::Lexer;
+use crate::parser::Parser;
+
+//the ast as s-expressions - one line per statement, with every node in brackets led by what it is, e.g. "let x = 1 + 2 * 3;" is "(let x (+ 1 (* 2 3)))".
+//unlike printing the ast back out as monkey, nothing is left for precedence to decide, so it's for checking what the parser made of some source:
+//  let a = b;            (let a b)
+//  return a;             (return a)
+//  a;                    a
+//  { a; b; }             (block a b)
+//  -a / (a + b)          (/ (- a) (+ a b))
+//  if (a) { b } else {}  (if a (block b) (block))
+//  fn(a, b) { a }        (fn (a b) (block a))
+//  f(a, b)               (call f a b)
+//  a = b                 (= a b)
+//spans are left out, so the dump only changes when the shape of the ast does
+pub fn sexp(program: &Program) -> String {
+    let mut out: String = String::new();
+    for statement in &program.statements {
+        write_statement(&mut out, statement);
+        out.push('\n');
+    }
+    out
+}
+
+//the s-expressions of the source's ast, or if it couldn't be parsed, its errors as "(error 1:5 "...")" - what the parser snapshot tests check
+pub fn parse_to_sexp(source: &str) -> String {
+    let mut parser: Parser = Parser::new(Lexer::new(source));
+    let program: Program = parser.parse_program();
+    if parser.errors().is_empty() {
+        return sexp(&program);
+    }
+
+    let mut out: String = String::new();
+    for (error, span) in parser.errors().iter().zip(parser.error_spans()) {
+        let (line, column) = span.line_column(source);
+        writeln!(out, "(error {line}:{column} {:?})", error.to_string()).unwrap();
+    }
+    out
+}
+
+fn write_statement(out: &mut String, statement: &Spanned<Statement>) {
+    match &statement.node {
+        Statement::Let(name, value) => {
+            write!(out, "(let {} ", name.node).unwrap();
+            write_expression(out, value);
+            out.push(')');
+        }
+        Statement::Return(value) => {
+            out.push_str("(return ");
+            write_expression(out, value);
+            out.push(')');
+        }
+        Statement::Expression(expression) => write_expression(out, expression),
+    }
+}
+
+fn write_block(out: &mut String, block: &Spanned<BlockStatement>) {
+    out.push_str("(block");
+    for statement in &block.statements {
+        out.push(' ');
+        write_statement(out, statement);
+    }
+    out.push(')');
+}
+
+fn write_expression(out: &mut String, expression: &Spanned<Expression>) {
+    match &expression.node {
+        Expression::Identifier(name) => out.push_str(name),
+        Expression::Integer(value) => write!(out, "{value}").unwrap(),
+        Expression::Boolean(value) => write!(out, "{value}").unwrap(),
+        Expression::Prefix(operator, right) => {
+            write!(out, "({operator} ").unwrap();
+            write_expression(out, right);
+            out.push(')');
+        }
+        Expression::Infix(operator, left, right) => {
+            write!(out, "({operator} ").unwrap();
+            write_expression(out, left);
+            out.push(' ');
+            write_expression(out, right);
+            out.push(')');
+        }
+        Expression::If {
+            condition,
+            consequence,
+            alternative,
+        } => {
+            out.push_str("(if ");
+            write_expression(out, condition);
+            out.push(' ');
+            write_block(out, consequence);
+            if let Some(alternative) = alternative {
+                out.push(' ');
+                write_block(out, alternative);
+            }
+            out.push(')');
+        }
+        Expression::Function { parameters, body } => {
+            let names: Vec<&str> = parameters
+                .iter()
+                .map(|parameter| parameter.as_str())
+                .collect();
+            write!(out, "(fn ({}) ", names.join(" ")).unwrap();
+            write_block(out, body);
+            out.push(')');
+        }
+        Expression::Call {
+            function,
+            arguments,
+        } => {
+            out.push_str("(call ");
+            write_expression(out, function);
+            for argument in arguments {
+                out.push(' ');
+                write_expression(out, argument);
+            }
+            out.push(')');
+        }
+        Expression::Assign(name, value) => {
+            write!(out, "(= {} ", name.node).unwrap();
+            write_expression(out, value);
+            out.push(')');
+        }
+    }
+}
+
+#[test]
+fn test_sexp() {
+    let tests: Vec<(&str, &str)> = vec![
+        ("let x = 1 + 2 * 3;", "(let x (+ 1 (* 2 3)))\n"),
+        ("return -a / (a + b);", "(return (/ (- a) (+ a b)))\n"),
+        ("!true == false", "(== (! true) false)\n"),
+        ("if (a) { b } else {}", "(if a (block b) (block))\n"),
+        ("if (a < b) { a; b }", "(if (< a b) (block a b))\n"),
+        ("fn(a, b) { a }", "(fn (a b) (block a))\n"),
+        (
+            "fn() { fn(x) { x }(1) }()",
+            "(call (fn () (block (call (fn (x) (block x)) 1))))\n",
+        ),
+        (
+            "f(a, g(b)); a && b || c",
+            "(call f a (call g b))\n(|| (&& a b) c)\n",
+        ),
+    ];
+
+    tests.into_iter().for_each(|(input, expected)| {
+        assert_eq!(parse_to_sexp(input), expected, "{input}");
+    });
+}
+
+#[test]
+fn test_parse_to_sexp_errors() {
+    assert_eq!(
+        parse_to_sexp("let x = 1;\nlet = 5;"),
+        "(error 2:5 \"expected an identifier, got \\\"=\\\"\")\n"
+    );
+}
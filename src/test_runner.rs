@@ -0,0 +1,152 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::ast::{Expression, Program, Statement};
+use crate::environment::Environment;
+use crate::evaluator::{call_function, eval_program};
+use crate::object::{Object, RuntimeError};
+use crate::token::Span;
+
+//a test found in a program, and how running it went
+#[derive(Debug, PartialEq)]
+pub struct TestResult {
+    pub name: String,
+    //the name of the test where it is bound, e.g. "test_add" in "let test_add = fn() { ... };"
+    pub span: Span,
+    //None if the test passed - an error that didn't say where it happened is given the test's span
+    pub error: Option<RuntimeError>,
+}
+
+//the tests in a program are the functions bound by let statements at the top of it with names starting "test_" - e.g. "let test_add = fn() { assert_eq(1 + 1, 2) };".
+//each is run in isolation, in order - the program is evaluated again in a fresh environment, then the test is called with no arguments, and passes if that doesn't give an error.
+//returns the error if evaluating the program itself gives one, as then no test can be run - the program is always evaluated, so this is found even if it has no tests
+pub fn run_tests(program: &Program) -> Result<Vec<TestResult>, RuntimeError> {
+    let tests: Vec<(&str, Span)> = program
+        .statements
+        .iter()
+        .filter_map(|statement| match &statement.node {
+            Statement::Let(name, value)
+                if name.starts_with("test_")
+                    && matches!(value.node, Expression::Function { .. }) =>
+            {
+                Some((name.as_str(), name.span))
+            }
+            _ => None,
+        })
+        .collect();
+
+    //the first test is run in the environment the program was first evaluated in, and each after it in a fresh one
+    let mut env: Rc<RefCell<Environment>> = evaluate(program)?;
+    let mut results: Vec<TestResult> = Vec::new();
+    for (i, (name, span)) in tests.into_iter().enumerate() {
+        if i > 0 {
+            env = evaluate(program)?;
+        }
+
+        let test: Object = env.borrow().get(name).unwrap_or(Object::Null);
+        let error: Option<RuntimeError> = match call_function(test, Vec::new()) {
+            Object::Error(error) => Some(error.with_span(span)),
+            _ => None,
+        };
+        results.push(TestResult {
+            name: name.to_string(),
+            span,
+            error,
+        });
+    }
+    Ok(results)
+}
+
+//evaluates the program in a new environment, returning the environment it leaves behind
+fn evaluate(program: &Program) -> Result<Rc<RefCell<Environment>>, RuntimeError> {
+    let env: Rc<RefCell<Environment>> = Rc::new(RefCell::new(Environment::new()));
+    match eval_program(program, &env) {
+        Object::Error(error) => Err(error),
+        _ => Ok(env),
+    }
+}
+
+#[cfg(test)]
+use crate::{lexer::Lexer, parser::Parser};
+
+#[test]
+fn test_run_tests() {
+    let source: &str = "
+let double = fn(x) { x * 2 };
+let test_double = fn() { assert_eq(double(2), 4) };
+let test_fails = fn() { assert(double(1) > 2, 1) };
+let helper = fn() { assert(false) };
+let test_wrong = fn(x) { x };
+let test_not_a_function = 5;
+";
+    let program: Program = Parser::new(Lexer::new(source)).parse_program();
+    let results: Vec<(String, &str, Option<String>)> = run_tests(&program)
+        .unwrap()
+        .into_iter()
+        .map(|result| {
+            let span: Span = result
+                .error
+                .as_ref()
+                .and_then(|error| error.span)
+                .unwrap_or(result.span);
+            (
+                result.name,
+                &source[span.start..span.end],
+                result.error.map(|error| error.message),
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        results,
+        vec![
+            ("test_double".to_string(), "test_double", None),
+            (
+                "test_fails".to_string(),
+                "assert(double(1) > 2, 1)",
+                Some("assertion failed: 1".to_string())
+            ),
+            (
+                "test_wrong".to_string(),
+                "test_wrong",
+                Some("wrong number of arguments: expected 1, got 0".to_string())
+            ),
+        ]
+    );
+}
+
+//a test can't see what another test changed
+#[test]
+fn test_tests_are_isolated() {
+    let program: Program = Parser::new(Lexer::new(
+        "let n = 0; let test_a = fn() { n = n + 1; assert_eq(n, 1) }; let test_b = fn() { n = n + 1; assert_eq(n, 1) };",
+    ))
+    .parse_program();
+    let errors: Vec<Option<RuntimeError>> = run_tests(&program)
+        .unwrap()
+        .into_iter()
+        .map(|result| result.error)
+        .collect();
+    assert_eq!(errors, vec![None, None]);
+}
+
+#[test]
+fn test_run_tests_program_error() {
+    let tests: Vec<&str> = vec![
+        "let test_a = fn() { 1 }; let test_b = fn() { 2 }; 1 / 0",
+        //the error is still found with no tests to run
+        "let x = 1 / 0;",
+    ];
+
+    tests.into_iter().for_each(|source| {
+        let program: Program = Parser::new(Lexer::new(source)).parse_program();
+        assert_eq!(
+            run_tests(&program).map_err(|error| error.message),
+            Err("division by zero".to_string()),
+            "{source}"
+        );
+    });
+
+    let program: Program = Parser::new(Lexer::new("let x = 1;")).parse_program();
+    assert_eq!(run_tests(&program), Ok(Vec::new()));
+}
@@ -1,5 +1,7 @@
 #![allow(dead_code)]
 
+use std::fmt;
+
 //[TODO] - remove Token::EndOfFile and replace with just the None enum to simplify code
 
 //every type of token that could exist in the code, so code can be broken up into chunks - e.g. let i = 2; becomes [Token::Let, Token::Identifier("i"), Token::Assign, Token::Integer("2"), Token::SemiColon, Token::EndOfFile]
@@ -13,16 +15,21 @@ pub enum Token<'source> {
     Integer(i32),             //"1343456", "7", "34"
 
     //Operators
-    Assign,      //"="
-    Plus,        //"+"
-    Minus,       //"-"
-    Bang,        //"!"
-    Asterisk,    //"*"
-    Slash,       //"/"
-    LessThan,    //"<"
-    GreaterThan, //">"
-    Equal,       //"=="
-    NotEqual,    //"!="
+    Assign,           //"="
+    Plus,             //"+"
+    Minus,            //"-"
+    Bang,             //"!"
+    Asterisk,         //"*"
+    Slash,            //"/"
+    Percent,          //"%"
+    LessThan,         //"<"
+    GreaterThan,      //">"
+    LessThanEqual,    //"<="
+    GreaterThanEqual, //">="
+    Equal,            //"=="
+    NotEqual,         //"!="
+    And,              //"&&"
+    Or,               //"||"
 
     //Delimiters
     Comma,            //","
@@ -42,6 +49,106 @@ pub enum Token<'source> {
     Return,   //"return"
 }
 
+//how a token is described in messages, e.g. in parser errors - symbols and keywords are quoted as they are written
+impl<'source> fmt::Display for Token<'source> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol: &str = match self {
+            Token::Illegal => return write!(f, "an illegal character"),
+            Token::EndOfFile => return write!(f, "the end of the input"),
+            Token::Identifier(ident) => return write!(f, "the identifier \"{ident}\""),
+            Token::Integer(value) => return write!(f, "the integer {value}"),
+            Token::Assign => "=",
+            Token::Plus => "+",
+            Token::Minus => "-",
+            Token::Bang => "!",
+            Token::Asterisk => "*",
+            Token::Slash => "/",
+            Token::Percent => "%",
+            Token::LessThan => "<",
+            Token::GreaterThan => ">",
+            Token::LessThanEqual => "<=",
+            Token::GreaterThanEqual => ">=",
+            Token::Equal => "==",
+            Token::NotEqual => "!=",
+            Token::And => "&&",
+            Token::Or => "||",
+            Token::Comma => ",",
+            Token::Semicolon => ";",
+            Token::LeftParenthesis => "(",
+            Token::RightParenthesis => ")",
+            Token::LeftBrace => "{",
+            Token::RightBrace => "}",
+            Token::Function => "fn",
+            Token::Let => "let",
+            Token::True => "true",
+            Token::False => "false",
+            Token::If => "if",
+            Token::Else => "else",
+            Token::Return => "return",
+        };
+        write!(f, "\"{symbol}\"")
+    }
+}
+
+//an owned version of a token, for when it has to outlive the input it was read from (e.g. when the input is streamed in rather than read all at once)
+#[derive(Debug, PartialEq, Clone)]
+pub enum OwnedToken {
+    Identifier(String),
+    //every other type of token, as none of them borrow from the input
+    Other(Token<'static>),
+}
+
+impl OwnedToken {
+    //borrows the token back as a normal token, e.g. so it can be matched on or compared with one
+    pub fn as_token(&self) -> Token<'_> {
+        match self {
+            OwnedToken::Identifier(ident) => Token::Identifier(ident),
+            OwnedToken::Other(token) => *token,
+        }
+    }
+}
+
+impl<'source> From<Token<'source>> for OwnedToken {
+    fn from(token: Token<'source>) -> Self {
+        let token: Token<'static> = match token {
+            Token::Identifier(ident) => return OwnedToken::Identifier(ident.to_string()),
+            Token::Integer(value) => Token::Integer(value),
+            Token::Illegal => Token::Illegal,
+            Token::EndOfFile => Token::EndOfFile,
+            Token::Assign => Token::Assign,
+            Token::Plus => Token::Plus,
+            Token::Minus => Token::Minus,
+            Token::Bang => Token::Bang,
+            Token::Asterisk => Token::Asterisk,
+            Token::Slash => Token::Slash,
+            Token::Percent => Token::Percent,
+            Token::LessThan => Token::LessThan,
+            Token::GreaterThan => Token::GreaterThan,
+            Token::LessThanEqual => Token::LessThanEqual,
+            Token::GreaterThanEqual => Token::GreaterThanEqual,
+            Token::Equal => Token::Equal,
+            Token::NotEqual => Token::NotEqual,
+            Token::And => Token::And,
+            Token::Or => Token::Or,
+            Token::Comma => Token::Comma,
+            Token::Semicolon => Token::Semicolon,
+            Token::LeftParenthesis => Token::LeftParenthesis,
+            Token::RightParenthesis => Token::RightParenthesis,
+            Token::LeftBrace => Token::LeftBrace,
+            Token::RightBrace => Token::RightBrace,
+            Token::Function => Token::Function,
+            Token::Let => Token::Let,
+            Token::True => Token::True,
+            Token::False => Token::False,
+            Token::If => Token::If,
+            Token::Else => Token::Else,
+            Token::Return => Token::Return,
+        };
+
+        OwnedToken::Other(token)
+    }
+}
+
 //[TODO] - store each Token as a value and a pos for debuging purposes and for code cleanliness
 /*
 pub struct Token<'source> {
@@ -50,16 +157,52 @@ pub struct Token<'source> {
 }
 */
 
-pub fn lookup_ident(ident: &str) -> Token {
-    match ident {
-        "fn" => Token::Function,
-        "let" => Token::Let,
-        "true" => Token::True,
-        "false" => Token::False,
-        "if" => Token::If,
-        "else" => Token::Else,
-        "return" => Token::Return,
-        ident => Token::Identifier(ident),
+//a range of byte offsets into the source code (start inclusive, end exclusive) - used to say where a token, node or error came from
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    //converts the start of the span into a 1-indexed (line, column) pair of the source it was taken from, counting columns in chars rather than bytes.
+    //a start part way through a char (or past the end) is moved back to the start of that char, so a span from different source can't panic
+    pub fn line_column(&self, source: &str) -> (usize, usize) {
+        let mut start: usize = self.start.min(source.len());
+        while !source.is_char_boundary(start) {
+            start -= 1;
+        }
+
+        let before: &str = &source[..start];
+        let line: usize = before.matches('\n').count() + 1;
+        let column: usize = match before.rfind('\n') {
+            Some(newline) => before[newline + 1..].chars().count() + 1,
+            None => before.chars().count() + 1,
+        };
+
+        (line, column)
+    }
+}
+
+//every keyword in monkey, and the token it is lexed as - anything else made of identifier chars is an identifier
+pub const KEYWORDS: [(&str, Token<'static>); 7] = [
+    ("fn", Token::Function),
+    ("let", Token::Let),
+    ("true", Token::True),
+    ("false", Token::False),
+    ("if", Token::If),
+    ("else", Token::Else),
+    ("return", Token::Return),
+];
+
+pub fn lookup_ident(ident: &str) -> Token<'_> {
+    match KEYWORDS.iter().find(|(keyword, _)| *keyword == ident) {
+        Some(&(_, token)) => token,
+        None => Token::Identifier(ident),
     }
 }
 
@@ -67,4 +210,37 @@ pub fn lookup_ident(ident: &str) -> Token {
 fn test() {
     assert_eq!(lookup_ident("fn"), Token::Function);
     assert_eq!(lookup_ident("test"), Token::Identifier("test"));
+    assert_eq!(lookup_ident("return"), Token::Return);
+    assert_eq!(lookup_ident("returns"), Token::Identifier("returns"));
+}
+
+#[test]
+fn test_owned_token() {
+    let input: String = String::from("name");
+    let tokens: Vec<OwnedToken> = vec![
+        Token::Identifier(&input).into(),
+        Token::Integer(5).into(),
+        Token::Let.into(),
+    ];
+    drop(input);
+
+    assert_eq!(tokens[0], OwnedToken::Identifier(String::from("name")));
+    assert_eq!(tokens[0].as_token(), Token::Identifier("name"));
+    assert_eq!(tokens[1].as_token(), Token::Integer(5));
+    assert_eq!(tokens[2], OwnedToken::Other(Token::Let));
+}
+
+#[test]
+fn test_span_line_column() {
+    let source: &str = "let x = 5;\nlet y = x / 0;";
+
+    assert_eq!(Span::new(0, 3).line_column(source), (1, 1));
+    assert_eq!(Span::new(4, 5).line_column(source), (1, 5));
+    assert_eq!(Span::new(21, 22).line_column(source), (2, 11));
+
+    //"é" is two bytes, so 5 is part way through the first one
+    let source: &str = "let é\nlet é = 1;";
+    assert_eq!(Span::new(5, 6).line_column(source), (1, 5));
+    assert_eq!(Span::new(11, 12).line_column(source), (2, 5));
+    assert_eq!(Span::new(100, 100).line_column(source), (2, 11));
 }
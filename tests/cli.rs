@@ -0,0 +1,97 @@
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Child, Command, Output, Stdio};
+
+//runs the monkey binary with the arguments, giving it the input on stdin
+fn monkey(arguments: &[&str], input: &str) -> Output {
+    let mut child: Child = Command::new(env!("CARGO_BIN_EXE_monkey-interpreter"))
+        .args(arguments)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+//every test in every file is run, and every failure (including a file that doesn't parse) is reported with where it happened
+#[test]
+fn test_test_subcommand() {
+    let dir: PathBuf = std::env::temp_dir().join(format!("monkey-cli-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(
+        dir.join("a.monkey"),
+        "let test_add = fn() { assert_eq(1 + 1, 2) };\nlet test_sub = fn() { assert_eq(1 - 1, 2) };",
+    )
+    .unwrap();
+    fs::write(dir.join("b.monkey"), "let = 1;").unwrap();
+
+    let output: Output = monkey(&["test", dir.to_str().unwrap()], "");
+    let stdout: String = String::from_utf8(output.stdout).unwrap();
+    let stderr: String = String::from_utf8(output.stderr).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let a: String = dir.join("a.monkey").display().to_string();
+    let b: String = dir.join("b.monkey").display().to_string();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stdout.contains(&format!("test {a}:1:5 test_add ... ok")),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains(&format!("test {a}:2:5 test_sub ... FAILED")),
+        "{stdout}"
+    );
+    assert!(
+        stdout.contains("test result: FAILED. 1 passed; 1 failed"),
+        "{stdout}"
+    );
+    assert_eq!(
+        stderr,
+        format!("{b}:1:5: expected an identifier, got \"=\"\n")
+    );
+}
+
+#[test]
+fn test_fmt_check_stdin() {
+    let output: Output = monkey(&["fmt", "--check"], "let  x=1;");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "<stdin> isn't formatted\n"
+    );
+
+    let output: Output = monkey(&["fmt", "--check"], "let x = 1;\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "");
+}
+
+//errors parsing a script are shown the same way, and on the same stream, as errors evaluating one
+#[test]
+fn test_script_errors() {
+    let tests: Vec<(&str, &str)> = vec![
+        (
+            "let x = 1;\nlet = 2;",
+            "ERROR: 2:5: expected an identifier, got \"=\"\n",
+        ),
+        ("let x = 1;\nx / 0", "ERROR: 2:1: division by zero\n"),
+    ];
+
+    tests.into_iter().for_each(|(input, expected)| {
+        let output: Output = monkey(&[], input);
+        assert!(!output.status.success(), "{input:?}");
+        assert_eq!(
+            String::from_utf8(output.stdout).unwrap(),
+            expected,
+            "{input:?}"
+        );
+        assert_eq!(String::from_utf8(output.stderr).unwrap(), "", "{input:?}");
+    });
+}
@@ -0,0 +1,29 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use monkey_interpreter::conformance::{backends, run_suite, update_suite, Backend, Failure};
+
+//runs the conformance suite in tests/conformance against every backend - see the conformance module for the files each program is checked against.
+//run with UPDATE_SNAPSHOTS=1 to write what the evaluator did to the expected files instead, then check the changes to them before committing
+#[test]
+fn test_conformance() {
+    let directory: PathBuf = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/conformance");
+    let backends: Vec<Box<dyn Backend>> = backends();
+
+    if env::var_os("UPDATE_SNAPSHOTS").is_some_and(|value| value != "0") {
+        update_suite(&directory, backends[0].as_ref()).unwrap();
+    }
+
+    let mut failures: Vec<Failure> = Vec::new();
+    for backend in &backends {
+        failures.extend(run_suite(&directory, backend.as_ref()).unwrap());
+    }
+
+    let report: Vec<String> = failures.iter().map(Failure::to_string).collect();
+    assert!(
+        failures.is_empty(),
+        "{} program(s) failed:\n{}",
+        failures.len(),
+        report.join("\n")
+    );
+}
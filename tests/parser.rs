@@ -0,0 +1,59 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use monkey_interpreter::sexp::parse_to_sexp;
+
+//snapshot tests of the parser - each tests/parser/*.monkey file is parsed and its s-expression dump compared with the .sexp file next to it.
+//run with UPDATE_SNAPSHOTS=1 to write the dumps to the .sexp files instead, then check the changes to them before committing
+#[test]
+fn test_parser_snapshots() {
+    let directory: PathBuf = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/parser");
+    let update: bool = env::var_os("UPDATE_SNAPSHOTS").is_some_and(|value| value != "0");
+
+    let mut files: Vec<PathBuf> = fs::read_dir(&directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "monkey")
+        })
+        .collect();
+    files.sort();
+    assert!(
+        !files.is_empty(),
+        "no .monkey files in {}",
+        directory.display()
+    );
+
+    let mut mismatched: Vec<String> = Vec::new();
+    for file in files {
+        let source: String = fs::read_to_string(&file).unwrap();
+        let actual: String = parse_to_sexp(&source);
+        let snapshot: PathBuf = file.with_extension("sexp");
+
+        if update {
+            fs::write(&snapshot, &actual).unwrap();
+            continue;
+        }
+
+        match fs::read_to_string(&snapshot) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => mismatched.push(format!(
+                "{} doesn't match {}:\n--- expected\n{expected}--- actual\n{actual}",
+                file.display(),
+                snapshot.display()
+            )),
+            Err(err) => mismatched.push(format!(
+                "couldn't read {}: {err}\n--- actual\n{actual}",
+                snapshot.display()
+            )),
+        }
+    }
+
+    assert!(
+        mismatched.is_empty(),
+        "{}\nrun with UPDATE_SNAPSHOTS=1 to update the snapshots",
+        mismatched.join("\n")
+    );
+}